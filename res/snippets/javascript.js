@@ -0,0 +1,25 @@
+function debounce(fn, delay) {
+  let timer = null;
+  return (...args) => {
+    clearTimeout(timer);
+    timer = setTimeout(() => fn(...args), delay);
+  };
+}
+
+const users = await fetch("/api/users")
+  .then((response) => response.json())
+  .catch((error) => console.error(error));
+
+class Counter {
+  constructor(start = 0) {
+    this.value = start;
+  }
+
+  increment() {
+    return ++this.value;
+  }
+}
+
+const evens = [1, 2, 3, 4, 5, 6].filter((n) => n % 2 === 0);
+const total = evens.reduce((sum, n) => sum + n, 0);
+console.log(`total: ${total}`);
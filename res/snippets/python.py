@@ -0,0 +1,30 @@
+def fibonacci(n):
+    a, b = 0, 1
+    for _ in range(n):
+        a, b = b, a + b
+    return a
+
+class Stack:
+    def __init__(self):
+        self.items = []
+
+    def push(self, item):
+        self.items.append(item)
+
+    def pop(self):
+        return self.items.pop()
+
+with open("data.txt") as f:
+    lines = [line.strip() for line in f if line.strip()]
+    print(len(lines), "lines")
+
+def word_count(text):
+    counts = {}
+    for word in text.split():
+        counts[word] = counts.get(word, 0) + 1
+    return sorted(counts.items(), key=lambda kv: -kv[1])
+
+try:
+    value = int(input("number: "))
+except ValueError as e:
+    print(f"not a number: {e}")
@@ -0,0 +1,38 @@
+fn main() {
+    let numbers = vec![1, 2, 3, 4, 5];
+    let sum: i32 = numbers.iter().sum();
+    println!("sum = {}", sum);
+}
+
+pub fn parse_pair(s: &str, separator: char) -> Option<(i32, i32)> {
+    let index = s.find(separator)?;
+    let left = s[..index].parse().ok()?;
+    let right = s[index + 1..].parse().ok()?;
+    Some((left, right))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Point {
+    pub x: f64,
+    pub y: f64,
+}
+
+impl Point {
+    pub fn distance(&self, other: &Point) -> f64 {
+        ((self.x - other.x).powi(2) + (self.y - other.y).powi(2)).sqrt()
+    }
+}
+
+fn count_words(text: &str) -> HashMap<&str, usize> {
+    let mut counts = HashMap::new();
+    for word in text.split_whitespace() {
+        *counts.entry(word).or_insert(0) += 1;
+    }
+    counts
+}
+
+match command {
+    Command::Add(x) => total += x,
+    Command::Reset => total = 0,
+    _ => return Err(Error::Unknown),
+}
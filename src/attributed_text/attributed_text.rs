@@ -1,14 +1,45 @@
 use orbtk::prelude::*;
+use rtyping::settings::{CaretStyle, TypedDisplay};
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum FontWeight {
+    Normal,
+    Bold
+}
+
+impl Default for FontWeight {
+    fn default() -> Self {
+        FontWeight::Normal
+    }
+}
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct AttributedLetter {
-    pub character: char,
-    pub color: Foreground
+    /// One grapheme cluster, drawn as a whole.
+    pub character: String,
+    pub color: Foreground,
+    /// Fill behind the letter.
+    pub background: Option<Brush>,
+    /// Lines under and through the letter, drawn in its color.
+    pub underline: bool,
+    pub strikethrough: bool,
+    pub weight: FontWeight,
+    /// What was typed instead of a wrong letter, see `ShowTyped`.
+    pub typed: Option<String>
 }
 
 impl AttributedLetter {
-    pub fn new(character: char, color: Foreground) -> Self{
-        AttributedLetter {character, color}
+    /// A plain letter, decorations are set on the returned value.
+    pub fn new(character: String, color: Foreground) -> Self{
+        AttributedLetter {
+            character,
+            color,
+            background: None,
+            underline: false,
+            strikethrough: false,
+            weight: FontWeight::default(),
+            typed: None
+        }
     }
 }
 
@@ -16,8 +47,43 @@ property!(
     AttributedText(Vec<AttributedLetter>)
 );
 
+property!(
+    /// Index of the letter the caret stands on.
+    CaretPosition(usize)
+);
+
+property!(
+    /// Shape of the caret.
+    Caret(CaretStyle)
+);
+
+property!(
+    /// Whether the word under the caret gets a background.
+    HighlightWord(bool)
+);
+
+property!(
+    /// Whether the caret glides to a new position instead of jumping there.
+    SmoothCaret(bool)
+);
+
+property!(
+    /// Where the `typed` text of letters is drawn.
+    ShowTyped(TypedDisplay)
+);
+
+property!(
+    /// Changed to have the text drawn again when nothing else changed, like while the caret glides.
+    RedrawRequest(u64)
+);
+
+property!(
+    /// Drawn in place of spaces, so their color shows whether they were typed.
+    SpaceSymbol(String)
+);
+
 impl AttributedText{
     pub fn to_string(&self) -> String{
-        self.0.iter().fold(String::new(), |mut rs, l| {rs.push(l.character); rs})
+        self.0.iter().fold(String::new(), |mut rs, l| {rs.push_str(&l.character); rs})
     }
 }
\ No newline at end of file
@@ -1,4 +1,5 @@
 use orbtk::prelude::*;
+use rtyping::settings::{CaretStyle, TypedDisplay};
 use crate::attributed_text_renderer::*;
 use crate::attributed_text_layout::*;
 use crate::attributed_text::attributed_text::*;
@@ -11,6 +12,27 @@ widget!(
         /// Sets or shares the text property.
         text: AttributedText,
 
+        /// Sets or shares the index of the letter the caret stands on.
+        caret_position: CaretPosition,
+
+        /// Sets or shares the shape of the caret.
+        caret: Caret,
+
+        /// Sets or shares whether the word under the caret is highlighted.
+        highlight_word: HighlightWord,
+
+        /// Sets or shares whether the caret glides between positions.
+        smooth_caret: SmoothCaret,
+
+        /// Sets or shares where the typed text of wrong letters is drawn.
+        show_typed: ShowTyped,
+
+        /// Sets or shares the counter bumped to draw the text again.
+        redraw_request: RedrawRequest,
+
+        /// Sets or shares what spaces are drawn as.
+        space_symbol: SpaceSymbol,
+
         /// Sets or shares the foreground property.
         foreground: Foreground,
 
@@ -29,13 +51,20 @@ impl Template for AttributedTextBlock {
     fn template(self, _: Entity, _: &mut BuildContext) -> Self {
         self.name("TextBlock")
             .text(vec![])
+            .caret_position(0)
+            .caret(CaretStyle::default())
+            .highlight_word(false)
+            .smooth_caret(true)
+            .show_typed(TypedDisplay::default())
+            .redraw_request(0)
+            .space_symbol("_".to_string())
             .foreground(colors::LINK_WATER_COLOR)
             .font_size(fonts::FONT_SIZE_12)
             .font("Roboto Regular")
     }
 
     fn render_object(&self) -> Option<Box<dyn RenderObject>> {
-        Some(Box::new(AttributedTextRenderObject))
+        Some(Box::new(AttributedTextRenderObject::new()))
     }
 
     fn layout(&self) -> Box<dyn Layout> {
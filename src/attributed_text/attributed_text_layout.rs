@@ -9,12 +9,15 @@ use orbtk::{prelude::*, render::RenderContext2D, tree::Tree};
 use orbtk::Layout;
 
 use crate::attributed_text::attributed_text::*;
+use crate::attributed_text_renderer::TAB_WIDTH;
 
 /// Fixed size layout is defined by fixed bounds like the size of an image or the size of a text.
 #[derive(Default)]
 pub struct AttributedTextLayout {
     desired_size: RefCell<DirtySize>,
     old_alignment: Cell<(Alignment, Alignment)>,
+    /// Width of every line of the text, before wrapping.
+    line_widths: RefCell<Vec<f64>>,
 }
 
 impl AttributedTextLayout {
@@ -54,17 +57,28 @@ impl Layout for AttributedTextLayout {
                     render_context_2_d.set_font_size(font_size.0);
                     render_context_2_d.set_font_family(&font.0[..]);
 
-                    let text_metrics =
-                        render_context_2_d.measure_text(text.to_string().as_str());
-
-                    let mut size = (text_metrics.width, text_metrics.height);
-
-                    if text.to_string().ends_with(" ") {
-                        size.0 += render_context_2_d
-                            .measure_text(&format!("{}a", text.to_string()))
-                            .width
-                            - render_context_2_d.measure_text("a").width;
+                    let tab = " ".repeat(TAB_WIDTH);
+                    let mut size = (0.0, 0.0);
+                    let mut line_widths = vec![];
+                    for line in text.to_string().replace('\t', &tab).split('\n') {
+                        let text_metrics = render_context_2_d.measure_text(line);
+                        let mut width = text_metrics.width;
+
+                        if line.ends_with(" ") {
+                            width += render_context_2_d
+                                .measure_text(&format!("{}a", line))
+                                .width
+                                - render_context_2_d.measure_text("a").width;
+                        }
+                        if width > size.0 {
+                            size.0 = width;
+                        }
+                        if text_metrics.height > size.1 {
+                            size.1 = text_metrics.height;
+                        }
+                        line_widths.push(width as f64);
                     }
+                    *self.line_widths.borrow_mut() = line_widths;
                     Some(size)
 
                 });
@@ -136,8 +150,12 @@ impl Layout for AttributedTextLayout {
             .component_store_mut()
             .borrow_mut_component::<Bounds>(entity)
         {
+            // Every line of the text takes at least one row and wraps into more when it is too long.
+            let rows: f64 = self.line_widths.borrow().iter()
+                .map(|width| (width / _parent_size.0).ceil().max(1.0))
+                .sum();
             bounds.set_width(self.desired_size.borrow().width().min(_parent_size.0));
-            bounds.set_height(self.desired_size.borrow().height()*(self.desired_size.borrow().width()/_parent_size.0).ceil());
+            bounds.set_height(self.desired_size.borrow().height()*rows);
         }
 
         if ecm.entity_store().children[&entity].len() > 0 {
@@ -1,8 +1,85 @@
+use std::cell::RefCell;
+use std::time::{Duration, Instant};
+
 use orbtk::prelude::*;
+use rtyping::settings::{CaretStyle, TypedDisplay};
 use crate::attributed_text::attributed_text::*;
 
-/// Used to render a text.
-pub struct AttributedTextRenderObject;
+/// Spaces a tab advances the text by.
+pub const TAB_WIDTH: usize = 4;
+
+/// Time the caret takes to glide to a new position when `SmoothCaret` is set.
+pub const CARET_ANIMATION: Duration = Duration::from_millis(80);
+
+const CARET_COLOR: &str = "#5DADE2";
+const WORD_HIGHLIGHT_COLOR: &str = "#34495E";
+/// Thickness of the line and underline carets.
+const CARET_WIDTH: f64 = 2.0;
+/// Thickness of underlines and strikethroughs of letters.
+const DECORATION_WIDTH: f64 = 1.5;
+/// Family bold letters are drawn in.
+const BOLD_FONT: &str = "Roboto Medium";
+/// Size of the typed text drawn above or below wrong letters, relative to the text.
+const TYPED_SCALE: f64 = 0.55;
+
+/// Where a letter is drawn: its left edge, its line and how far it advances the text.
+struct Placement {
+    x: f64,
+    line: f64,
+    width: f64,
+}
+
+/// Glide of the caret from where it was drawn last towards its position, in letter coordinates.
+struct CaretMotion {
+    from: (f64, f64),
+    to: (f64, f64),
+    started: Instant,
+}
+
+impl CaretMotion {
+    fn current(&self, now: Instant) -> (f64, f64) {
+        let t = (now.duration_since(self.started).as_secs_f64() / CARET_ANIMATION.as_secs_f64()).min(1.0);
+        // Ease out, so the caret starts fast and settles on the letter.
+        let eased = 1.0 - (1.0 - t).powi(3);
+        (
+            self.from.0 + (self.to.0 - self.from.0) * eased,
+            self.from.1 + (self.to.1 - self.from.1) * eased,
+        )
+    }
+}
+
+/// Used to render a text. Line breaks start a new line and tabs advance to the next column,
+/// both are marked with an arrow so their color shows whether they were typed, spaces are
+/// marked with the `SpaceSymbol`. A caret marks the
+/// letter at `CaretPosition`, optionally on top of a highlight of its whole word.
+#[derive(Default)]
+pub struct AttributedTextRenderObject {
+    caret: RefCell<Option<CaretMotion>>,
+}
+
+impl AttributedTextRenderObject {
+    pub fn new() -> Self {
+        AttributedTextRenderObject::default()
+    }
+
+    /// Where to draw the caret heading for `target`, starting a new glide when the target moved.
+    fn caret_position(&self, target: (f64, f64), smooth: bool) -> (f64, f64) {
+        let now = Instant::now();
+        let mut caret = self.caret.borrow_mut();
+        match &mut *caret {
+            Some(motion) if smooth => {
+                if motion.to != target {
+                    *motion = CaretMotion { from: motion.current(now), to: target, started: now };
+                }
+                motion.current(now)
+            }
+            _ => {
+                *caret = Some(CaretMotion { from: target, to: target, started: now });
+                target
+            }
+        }
+    }
+}
 
 impl Into<Box<dyn RenderObject>> for AttributedTextRenderObject {
     fn into(self) -> Box<dyn RenderObject> {
@@ -12,7 +89,7 @@ impl Into<Box<dyn RenderObject>> for AttributedTextRenderObject {
 
 impl RenderObject for AttributedTextRenderObject {
     fn render(&self, context: &mut Context<'_>, global_position: &Point) {
-        let (bounds, text, font, font_size) = {
+        let (bounds, text, font, font_size, caret, caret_style, highlight_word, smooth_caret, show_typed, space) = {
             let widget = context.widget();
             let text = widget.clone::<AttributedText>();
 
@@ -28,6 +105,12 @@ impl RenderObject for AttributedTextRenderObject {
                 txt,
                 widget.get::<Font>().0.clone(),
                 widget.get::<FontSize>().0,
+                widget.get::<CaretPosition>().0,
+                widget.get::<Caret>().0,
+                widget.get::<HighlightWord>().0,
+                widget.get::<SmoothCaret>().0,
+                widget.get::<ShowTyped>().0,
+                widget.get::<SpaceSymbol>().0.clone(),
             )
         };
 
@@ -35,29 +118,35 @@ impl RenderObject for AttributedTextRenderObject {
             context.render_context_2_d().save();
             context.render_context_2_d().begin_path();
 
-            context.render_context_2_d().set_font_family(font);
+            context.render_context_2_d().set_font_family(font.clone());
             context.render_context_2_d().set_font_size(font_size);
 
             let words = text.iter()
                 .fold(vec![vec![]], |mut rs, l| {
                     let len = rs.len();
                     rs[len-1].push(l);
-                    if l.character == '_' || l.character == ' ' {rs.push(vec![])};
+                    if l.character.chars().all(char::is_whitespace) {rs.push(vec![])};
                     rs
                 });
 
+            // Every letter is placed first, so the highlight and the caret can be drawn below the letters.
+            let mut placements: Vec<Placement> = Vec::with_capacity(text.len());
+            let mut word_ranges = vec![];
             let mut line = 1.0;
 
             let mut str = String::new();
             let mut x = 0.0;
 
-            for w in 0..words.len() {
-                let word = &words[w];
+            for word in &words {
                 let word_size = context
                     .render_context_2_d()
                     .measure_text(&word.iter()
                         .fold(String::new(), |mut rs, l| {
-                            rs.push(l.character);
+                            match l.character.as_str() {
+                                "\n" => {}
+                                "\t" => rs.push_str(&" ".repeat(TAB_WIDTH)),
+                                c => rs.push_str(symbol(c, &space))
+                            }
                             rs
                         }));
                 //println!("new x {}", x + word_size.width);
@@ -67,24 +156,125 @@ impl RenderObject for AttributedTextRenderObject {
                     str = String::new();
                 }
 
-                for i in 0..word.len() {
-                    let letter = word[i].clone();
-                    context.render_context_2_d().set_fill_style(letter.color.0);
-
-                    str.push(letter.character);
-                    let size = context.render_context_2_d().measure_text(&str);
-                    let width = size.width;
-                    context.render_context_2_d().fill_text(
-                        &letter.character.to_string(),
-                        global_position.x + bounds.x + x,
-                        global_position.y + bounds.y + (line as f64 + 1.0)*(size.height),
-                        None,
-                    );
-                    x = width;
+                word_ranges.push(placements.len()..placements.len() + word.len());
+                for letter in word {
+                    match letter.character.as_str() {
+                        "\n" => {
+                            let width = context.render_context_2_d().measure_text(symbol(&letter.character, &space)).width;
+                            placements.push(Placement { x, line, width });
+                            line += 1.0;
+                            str = String::new();
+                            x = 0.0;
+                            continue;
+                        }
+                        "\t" => str.push_str(&" ".repeat(TAB_WIDTH)),
+                        c => str.push_str(symbol(c, &space))
+                    }
+                    let next_x = context.render_context_2_d().measure_text(&str).width;
+                    placements.push(Placement { x, line, width: next_x - x });
+                    x = next_x;
+                }
+            }
+
+            let height = context.render_context_2_d().measure_text("A").height;
+            let left = global_position.x + bounds.x;
+            let top = global_position.y + bounds.y;
+            // Letters are drawn on their baseline, boxes around them reach a bit below it.
+            let baseline = |line: f64| top + (line + 1.0) * height;
+            let descent = height * 0.25;
+
+            if highlight_word {
+                if let Some(range) = word_ranges.iter().find(|r| r.contains(&caret)) {
+                    context.render_context_2_d().set_fill_style(Brush::from(WORD_HIGHLIGHT_COLOR));
+                    for placement in &placements[range.clone()] {
+                        context.render_context_2_d().fill_rect(
+                            left + placement.x,
+                            baseline(placement.line) - height,
+                            placement.width,
+                            height + descent,
+                        );
+                    }
+                }
+            }
+
+            // The caret stands on the letter to type next, or right after the last one.
+            let (target, caret_width) = match placements.get(caret) {
+                Some(placement) => ((placement.x, placement.line), placement.width),
+                None => {
+                    let last = &placements[placements.len() - 1];
+                    ((last.x + last.width, last.line), context.render_context_2_d().measure_text("a").width)
+                }
+            };
+            let (caret_x, caret_line) = self.caret_position(target, smooth_caret);
+            let (caret_x, caret_y) = (left + caret_x, baseline(caret_line));
+            context.render_context_2_d().set_fill_style(Brush::from(CARET_COLOR));
+            if caret_style == CaretStyle::Block {
+                context.render_context_2_d().fill_rect(caret_x, caret_y - height, caret_width, height + descent);
+            }
+
+            for (letter, placement) in text.iter().zip(&placements) {
+                let (x, y) = (left + placement.x, baseline(placement.line));
+                if let Some(background) = &letter.background {
+                    context.render_context_2_d().set_fill_style(background.clone());
+                    context.render_context_2_d().fill_rect(x, y - height, placement.width, height + descent);
+                }
+
+                context.render_context_2_d().set_fill_style(letter.color.0.clone());
+                context.render_context_2_d().set_font_family(match letter.weight {
+                    FontWeight::Normal => font.clone(),
+                    FontWeight::Bold => BOLD_FONT.to_string()
+                });
+                let typed = letter.typed.as_ref().filter(|_| show_typed != TypedDisplay::Hidden);
+                let shown = match typed {
+                    Some(typed) if show_typed == TypedDisplay::InPlace => typed,
+                    _ => &letter.character
+                };
+                context.render_context_2_d().fill_text(symbol(shown, &space), x, y, None);
+                if let Some(typed) = typed {
+                    // Drawn in small print in the space between the lines.
+                    let typed_y = match show_typed {
+                        TypedDisplay::Above => Some(y - height),
+                        TypedDisplay::Below => Some(y + height * TYPED_SCALE),
+                        _ => None
+                    };
+                    if let Some(typed_y) = typed_y {
+                        context.render_context_2_d().set_font_size(font_size * TYPED_SCALE);
+                        context.render_context_2_d().fill_text(symbol(typed, &space), x, typed_y, None);
+                        context.render_context_2_d().set_font_size(font_size);
+                    }
+                }
+
+                if letter.underline {
+                    context.render_context_2_d()
+                        .fill_rect(x, y + descent / 2.0, placement.width, DECORATION_WIDTH);
+                }
+                if letter.strikethrough {
+                    context.render_context_2_d()
+                        .fill_rect(x, y - height * 0.3, placement.width, DECORATION_WIDTH);
                 }
             }
+
+            context.render_context_2_d().set_fill_style(Brush::from(CARET_COLOR));
+            match caret_style {
+                CaretStyle::Line => context.render_context_2_d()
+                    .fill_rect(caret_x - CARET_WIDTH / 2.0, caret_y - height, CARET_WIDTH, height + descent),
+                CaretStyle::Underline => context.render_context_2_d()
+                    .fill_rect(caret_x, caret_y + descent - CARET_WIDTH, caret_width, CARET_WIDTH),
+                CaretStyle::Block => {}
+            }
+
             context.render_context_2_d().close_path();
             context.render_context_2_d().restore();
         }
     }
 }
+
+/// Line breaks and tabs are drawn as arrows, spaces as `space`.
+fn symbol<'a>(character: &'a str, space: &'a str) -> &'a str {
+    match character {
+        "\n" => "\u{21B5}",
+        "\t" => "\u{2192}",
+        " " => space,
+        c => c
+    }
+}
@@ -0,0 +1,181 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+/// Words per sentence, sentences start capitalized and end with a period.
+const MIN_SENTENCE: usize = 4;
+const MAX_SENTENCE: usize = 12;
+
+/// Chance of a comma after a word inside a sentence.
+const COMMA_RATE: f64 = 0.1;
+/// Chances of a word being quoted or put in parentheses.
+const QUOTE_RATE: f64 = 0.03;
+const PARENTHESES_RATE: f64 = 0.03;
+/// Chances of a sentence ending with a question or an exclamation mark instead of a period.
+const QUESTION_RATE: f64 = 0.1;
+const EXCLAMATION_RATE: f64 = 0.05;
+/// Chance of a word being replaced by a number.
+const NUMBER_RATE: f64 = 0.1;
+const MAX_NUMBER: u32 = 9999;
+
+/// Turns plain lowercase words into text that exercises the shift, symbol and number keys.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Augmentation {
+    /// Capitalize the first word of every sentence.
+    pub capitals: bool,
+    /// End sentences with a period and sprinkle in commas, quotes and parentheses.
+    pub punctuation: bool,
+    /// Replace some of the words by numbers.
+    pub numbers: bool,
+}
+
+impl Augmentation {
+    pub fn is_enabled(&self) -> bool {
+        self.capitals || self.punctuation || self.numbers
+    }
+
+    pub fn description(&self) -> String {
+        let enabled: Vec<&str> = [
+            (self.capitals, "capitals"),
+            (self.punctuation, "punctuation"),
+            (self.numbers, "numbers"),
+        ].iter()
+            .filter(|(on, _)| *on)
+            .map(|(_, name)| *name)
+            .collect();
+        if enabled.is_empty() {
+            "plain".to_string()
+        } else {
+            enabled.join(", ")
+        }
+    }
+
+    pub fn apply<R: Rng>(&self, words: Vec<String>, rng: &mut R) -> Vec<String> {
+        if !self.is_enabled() {
+            return words;
+        }
+
+        let mut text = Vec::with_capacity(words.len());
+        let mut sentence_left = 0;
+        for word in words {
+            let mut word = if self.numbers && rng.gen_bool(NUMBER_RATE) {
+                rng.gen_range(0, MAX_NUMBER + 1).to_string()
+            } else {
+                word
+            };
+
+            let sentence_start = sentence_left == 0;
+            if sentence_start {
+                sentence_left = rng.gen_range(MIN_SENTENCE, MAX_SENTENCE + 1);
+            }
+            sentence_left -= 1;
+
+            if self.capitals && sentence_start {
+                word = capitalize(&word);
+            }
+            if self.punctuation {
+                if rng.gen_bool(QUOTE_RATE) {
+                    word = format!("\"{}\"", word);
+                } else if rng.gen_bool(PARENTHESES_RATE) {
+                    word = format!("({})", word);
+                }
+                if sentence_left == 0 {
+                    word.push(sentence_end(rng));
+                } else if rng.gen_bool(COMMA_RATE) {
+                    word.push(',');
+                }
+            }
+            text.push(word);
+        }
+
+        // The text may stop in the middle of a sentence.
+        if self.punctuation {
+            if let Some(last) = text.last_mut() {
+                if !last.ends_with(['.', '?', '!']) {
+                    last.push('.');
+                }
+            }
+        }
+        text
+    }
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+fn sentence_end<R: Rng>(rng: &mut R) -> char {
+    let roll = rng.gen::<f64>();
+    if roll < QUESTION_RATE {
+        '?'
+    } else if roll < QUESTION_RATE + EXCLAMATION_RATE {
+        '!'
+    } else {
+        '.'
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    fn words(n: usize) -> Vec<String> {
+        (0..n).map(|i| ["alpha", "bravo", "charlie"][i % 3].to_string()).collect()
+    }
+
+    fn augment(augmentation: Augmentation, n: usize) -> Vec<String> {
+        augmentation.apply(words(n), &mut ChaCha8Rng::seed_from_u64(1))
+    }
+
+    #[test]
+    fn plain_words_are_left_alone() {
+        assert_eq!(augment(Augmentation::default(), 20), words(20));
+        assert_eq!(Augmentation::default().description(), "plain");
+        let augmentation = Augmentation { capitals: true, numbers: true, ..Augmentation::default() };
+        assert_eq!(augmentation.description(), "capitals, numbers");
+    }
+
+    #[test]
+    fn punctuation_ends_every_sentence() {
+        let text = augment(Augmentation { punctuation: true, capitals: true, ..Augmentation::default() }, 500);
+        assert_eq!(text.len(), 500);
+        assert!(text[0].starts_with(char::is_uppercase));
+        assert!(text.last().unwrap().ends_with(['.', '?', '!']));
+
+        let mut sentence = 0;
+        for (i, word) in text.iter().enumerate() {
+            let bare = word.trim_matches(|c: char| !c.is_alphabetic());
+            assert_eq!(bare.to_lowercase(), words(500)[i], "{}", word);
+            // Only the first word of a sentence is capitalized.
+            assert_eq!(bare.starts_with(char::is_uppercase), sentence == 0, "{}", word);
+            sentence += 1;
+            if word.ends_with(['.', '?', '!']) {
+                assert!((MIN_SENTENCE..=MAX_SENTENCE).contains(&sentence) || i == text.len() - 1);
+                sentence = 0;
+            }
+        }
+        assert!(text.iter().any(|w| w.ends_with(',')));
+    }
+
+    #[test]
+    fn numbers_replace_some_words() {
+        let text = augment(Augmentation { numbers: true, ..Augmentation::default() }, 500);
+        let numbers: Vec<u32> = text.iter().filter_map(|w| w.parse().ok()).collect();
+        assert!(!numbers.is_empty());
+        assert!(numbers.iter().all(|n| *n <= MAX_NUMBER));
+        let plain = text.iter().zip(words(500)).filter(|(w, original)| *w == original).count();
+        assert_eq!(plain + numbers.len(), 500);
+    }
+
+    #[test]
+    fn capitals_handle_other_alphabets() {
+        assert_eq!(capitalize("ёж"), "Ёж");
+        assert_eq!(capitalize(""), "");
+    }
+}
@@ -0,0 +1,373 @@
+//! Terminal front end running the same practice loop as the OrbTk window.
+//! Pass the path of a text or Markdown file to practice on it instead of random words, and
+//! `--seed N` to get the same texts as everyone else using that seed.
+extern crate crossterm;
+
+use std::cell::RefCell;
+use std::env;
+use std::io::{stdout, Stdout, Write};
+use std::process;
+use std::rc::Rc;
+use std::time::Duration;
+
+use crossterm::{
+    cursor::{Hide, MoveTo, Show},
+    event::{self, Event, KeyCode, KeyEvent, KeyModifiers},
+    execute, queue,
+    style::{Color, Print, ResetColor, SetForegroundColor},
+    terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen},
+    Result,
+};
+
+use rtyping::document::DocumentSource;
+use rtyping::options::Options;
+use rtyping::settings::{personal_best_text, visible, Settings};
+use rtyping::typing_session::*;
+use rtyping::typing_statistic::TypingStatistic;
+use rtyping::word_list::{self, WordList};
+use rtyping::word_source::WordSource;
+
+/// Row of the status line, telling what failed to load or save.
+const STATUS_ROW: u16 = 5;
+/// Row the practice text starts at, below the counters, the label, the key help and the status.
+const TEXT_ROW: u16 = 7;
+
+/// Columns a tab advances the text by.
+const TAB_WIDTH: u16 = 4;
+
+/// Column the substitutions are listed at, right of the key table of the results.
+const SUBSTITUTIONS_COLUMN: u16 = 36;
+/// Substitutions listed on the results of a test.
+const RESULT_SUBSTITUTIONS: usize = 8;
+
+fn main() -> Result<()> {
+    let options = Options::parse(env::args().skip(1)).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        process::exit(2);
+    });
+    let mut settings = Settings::load();
+    let mut status = None;
+    let mut words = WordSource::new(settings.load_words(&mut status), settings.lesson(), 20);
+    words.set_mode(settings.generation, settings.adaptive_strength);
+    words.set_augmentation(settings.augmentation);
+    let words = Rc::new(RefCell::new(words));
+    let source: Box<dyn TextSource> = match options.document {
+        Some(path) => Box::new(DocumentSource::open(&path)?),
+        None => settings.content.source(&words),
+    };
+
+    let mut session = TypingSession::new(
+        source,
+        Box::new(SystemClock),
+        TypingStatistic::load(),
+    );
+    session.set_error_policy(settings.error_policy);
+    session.set_mode(settings.test_mode);
+    if options.seed.is_some() {
+        session.set_seed(options.seed);
+    }
+
+    let mut stdout = stdout();
+    let _terminal = TerminalGuard::enter(&mut stdout)?;
+    run(&mut stdout, &mut session, &words, &mut settings, status)
+}
+
+/// Keeps the terminal in raw mode on the alternate screen, restores it when dropped, including
+/// on errors and panics.
+struct TerminalGuard;
+
+impl TerminalGuard {
+    fn enter(stdout: &mut Stdout) -> Result<TerminalGuard> {
+        terminal::enable_raw_mode()?;
+        let guard = TerminalGuard;
+        execute!(stdout, EnterAlternateScreen, Hide)?;
+        Ok(guard)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let _ = execute!(stdout(), Show, LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+fn run(
+    stdout: &mut Stdout,
+    session: &mut TypingSession,
+    words: &Rc<RefCell<WordSource>>,
+    settings: &mut Settings,
+    mut status: Option<String>,
+) -> Result<()> {
+    loop {
+        draw(stdout, session, settings, status.as_deref())?;
+
+        // Keep redrawing while the user pauses so the speed and the countdown stay current.
+        let timeout = if session.remaining().is_some() { 100 } else { 500 };
+        let outcome = if event::poll(Duration::from_millis(timeout))? {
+            match event::read()? {
+                Event::Key(event) => match handle_key(event, session, words, settings, &mut status) {
+                    Some(outcome) => outcome,
+                    None => return Ok(()),
+                },
+                _ => continue,
+            }
+        } else {
+            session.tick()
+        };
+
+        if outcome == Outcome::Finished {
+            if let Err(e) = session.statistic().save() {
+                status = Some(format!("failed to save statistic: {}", e));
+            }
+        }
+    }
+}
+
+/// Applies a key to the session or the settings, returns `None` when the user quits. Failures
+/// of a settings change replace the `status`.
+fn handle_key(
+    event: KeyEvent,
+    session: &mut TypingSession,
+    words: &Rc<RefCell<WordSource>>,
+    settings: &mut Settings,
+    status: &mut Option<String>,
+) -> Option<Outcome> {
+    let control = event.modifiers.contains(KeyModifiers::CONTROL);
+    let outcome = match event.code {
+        KeyCode::Esc => return None,
+        KeyCode::Char('c') if control => return None,
+        // Terminals send ctrl+backspace as ctrl+h, ctrl+w is the usual word deletion.
+        KeyCode::Char('h') | KeyCode::Char('w') if control => session.delete_word(),
+        KeyCode::Backspace => session.backspace(),
+        KeyCode::Enter => session.press("\n"),
+        KeyCode::Tab => session.press("\t"),
+        // The terminal composes dead keys and input method text into the chars it sends.
+        KeyCode::Char(key) if !control => session.press(&key.to_string()),
+        KeyCode::F(5) => {
+            session.restart();
+            Outcome::Ignored
+        }
+        KeyCode::F(6) => {
+            session.retry();
+            Outcome::Ignored
+        }
+        KeyCode::F(key @ 1..=12) => {
+            *status = None;
+            match key {
+                1 => {
+                    settings.lesson = !settings.lesson;
+                    words.borrow_mut().set_lesson(settings.lesson());
+                    session.restart();
+                }
+                2 => {
+                    settings.error_policy = settings.error_policy.toggled();
+                    session.set_error_policy(settings.error_policy);
+                }
+                3 => {
+                    settings.generation = settings.generation.next();
+                    words.borrow_mut().set_mode(settings.generation, settings.adaptive_strength);
+                }
+                4 => {
+                    settings.test_mode = settings.test_mode.next();
+                    session.set_mode(settings.test_mode);
+                }
+                7 => {
+                    if let Some(name) = WordList::next_name(&settings.word_list) {
+                        settings.word_list = name;
+                    }
+                    words.borrow_mut().set_generator(settings.load_words(status));
+                    session.restart();
+                }
+                8 => {
+                    settings.word_limit = word_list::next_limit(settings.word_limit);
+                    words.borrow_mut().set_generator(settings.load_words(status));
+                    session.restart();
+                }
+                9..=11 => {
+                    let augmentation = &mut settings.augmentation;
+                    match key {
+                        9 => augmentation.capitals = !augmentation.capitals,
+                        10 => augmentation.punctuation = !augmentation.punctuation,
+                        _ => augmentation.numbers = !augmentation.numbers,
+                    }
+                    words.borrow_mut().set_augmentation(settings.augmentation);
+                    session.restart();
+                }
+                _ => {
+                    settings.content = settings.content.next();
+                    session.set_source(settings.content.source(words));
+                }
+            }
+            if let Err(e) = settings.save() {
+                *status = Some(format!("failed to save settings: {}", e));
+            }
+            Outcome::Ignored
+        }
+        _ => Outcome::Ignored,
+    };
+    Some(outcome)
+}
+
+fn draw(stdout: &mut Stdout, session: &TypingSession, settings: &Settings, status: Option<&str>) -> Result<()> {
+    let (width, _) = terminal::size()?;
+    let current_stat = session.current_state();
+
+    queue!(
+        stdout,
+        Clear(ClearType::All),
+        MoveTo(0, 0),
+        Print(format!(
+            "Speed: {:.1} cpm, {:.1} wpm, net {:.1} wpm",
+            current_stat.speed, current_stat.gross_wpm, current_stat.net_wpm
+        )),
+        MoveTo(0, 1),
+        Print(format!(
+            "Accuracy: {:.1}%, consistency: {:.1}%, errors: {}, corrections: {}",
+            current_stat.accuracy, current_stat.consistency, current_stat.errors, current_stat.corrections
+        )),
+        MoveTo(0, 2),
+        Print(format!("{} {}", timer_text(session), session.label())),
+        MoveTo(0, 3),
+        Print(format!(
+            "F2: {} | F3: {} text | F4: {} | F5: restart | F6: retry | F7: {} | F8: {} | Esc: quit",
+            session.error_policy().description(),
+            settings.generation.description(),
+            settings.test_mode.description(),
+            settings.word_list,
+            word_list::limit_description(settings.word_limit)
+        )),
+        MoveTo(0, 4),
+        Print(format!(
+            "F1: lesson {} | F9: capitals | F10: punctuation | F11: numbers ({}) | F12: {}",
+            if settings.lesson { "on" } else { "off" },
+            settings.augmentation.description(),
+            settings.content.description()
+        )),
+    )?;
+    if let Some(status) = status {
+        queue!(
+            stdout,
+            MoveTo(0, STATUS_ROW),
+            SetForegroundColor(color(Pressed::WrongPressed.color())),
+            Print(status),
+            ResetColor,
+        )?;
+    }
+
+    if session.is_locked() {
+        draw_results(stdout, session)?;
+    } else {
+        draw_text(stdout, session, width, settings.content.space_symbol())?;
+    }
+
+    queue!(stdout, ResetColor)?;
+    stdout.flush()?;
+    Ok(())
+}
+
+fn draw_text(stdout: &mut Stdout, session: &TypingSession, width: u16, space: &str) -> Result<()> {
+    let columns = |letter: &KeyLetter| if letter.character == "\t" { TAB_WIDTH } else { 1 };
+    let mut row = TEXT_ROW;
+    let mut column = 0;
+    for word in session.text().split_inclusive(|l| l.is_whitespace()) {
+        let word_width: u16 = word.iter().map(columns).sum();
+        if column > 0 && column + word_width > width {
+            row += 1;
+            column = 0;
+        }
+        for letter in word {
+            // Spaces, line breaks and tabs are marked so their color shows whether they were typed.
+            let symbol = match letter.character.as_str() {
+                " " => space,
+                "\n" => "\u{21B5}",
+                "\t" => "\u{2192}",
+                c => c,
+            };
+            queue!(
+                stdout,
+                MoveTo(column, row),
+                SetForegroundColor(color(letter.pressed.color())),
+                Print(symbol),
+            )?;
+            if letter.character == "\n" {
+                row += 1;
+                column = 0;
+            } else {
+                column += columns(letter);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Replaces the text once a test is over, the slowest keys of the test come first.
+fn draw_results(stdout: &mut Stdout, session: &TypingSession) -> Result<()> {
+    let (_, height) = terminal::size()?;
+    let result = session.current_state();
+    queue!(
+        stdout,
+        MoveTo(0, TEXT_ROW),
+        Print(format!(
+            "Result: {:.1} wpm, net {:.1} wpm, accuracy {:.1}%, seed {}",
+            result.gross_wpm, result.net_wpm, result.accuracy, session.seed()
+        )),
+    )?;
+
+    // A finished passage shows where it is from and the best run on it.
+    let mut row = TEXT_ROW + 1;
+    if let Some(attribution) = session.attribution() {
+        queue!(stdout, MoveTo(0, row), Print(format!("From {}", attribution)))?;
+        row += 1;
+    }
+    if let Some(best) = session.statistic().last_sample_best() {
+        queue!(stdout, MoveTo(0, row), Print(personal_best_text(&best)))?;
+        row += 1;
+    }
+
+    queue!(stdout, MoveTo(0, row + 1), Print("Key  presses  errors  mean time"))?;
+    let rows = session.statistic().last_sample_keys().into_iter()
+        .filter(|(key, _)| key != " ")
+        .zip(row + 2..height);
+    for ((key, performance), row) in rows {
+        queue!(
+            stdout,
+            MoveTo(0, row),
+            Print(format!(
+                "{:<4} {:>7}  {:>6}  {:>6.0} ms",
+                key, performance.presses, performance.errors, performance.mean_time
+            )),
+        )?;
+    }
+
+    // Letters most often typed in place of others, over every test so far.
+    queue!(stdout, MoveTo(SUBSTITUTIONS_COLUMN, row + 1), Print("Common mistakes  count"))?;
+    let rows = session.statistic().common_substitutions(RESULT_SUBSTITUTIONS).into_iter()
+        .zip(row + 2..height);
+    for (substitution, row) in rows {
+        queue!(
+            stdout,
+            MoveTo(SUBSTITUTIONS_COLUMN, row),
+            Print(format!(
+                "{} \u{2192} {:<11} {:>5}",
+                visible(&substitution.expected), visible(&substitution.typed), substitution.count
+            )),
+        )?;
+    }
+    Ok(())
+}
+
+fn timer_text(session: &TypingSession) -> String {
+    match session.remaining() {
+        Some(_) if session.is_locked() => "Time is up, F5: next text, F6: retry.".to_string(),
+        Some(remaining) => format!("Time left: {:.1} s.", remaining.as_secs_f64()),
+        None if session.is_locked() => "Done, F5: next text, F6: retry.".to_string(),
+        None => String::new(),
+    }
+}
+
+/// Converts a `#RRGGBB` color into a terminal color.
+fn color(hex: &str) -> Color {
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).unwrap_or(0);
+    Color::Rgb { r: channel(1), g: channel(3), b: channel(5) }
+}
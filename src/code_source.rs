@@ -0,0 +1,180 @@
+use std::fs;
+use std::path::Path;
+
+use rand_chacha::ChaCha8Rng;
+use rand::{Rng, SeedableRng};
+
+use crate::storage;
+use crate::typing_session::TextSource;
+use crate::typing_statistic::TypingStatistic;
+
+/// Longer snippets are cut off after this many lines.
+const MAX_SNIPPET_LINES: usize = 15;
+
+/// A piece of code with its indentation and line breaks.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snippet {
+    pub language: String,
+    pub code: String,
+}
+
+/// Draws random snippets from the bundled corpus in `res/snippets` and the user's own files in
+/// the `snippets` config directory. Every file holds snippets of one language separated by blank lines.
+pub struct CodeSource {
+    snippets: Vec<Snippet>,
+    current: Option<usize>,
+    /// Snippet files that couldn't be read, shown in the label.
+    warning: Option<String>,
+    rng: ChaCha8Rng,
+}
+
+impl CodeSource {
+    pub fn load() -> CodeSource {
+        let (mut snippets, mut failures) = (vec![], vec![]);
+        for dir in [storage::config_dir().join("snippets"), storage::resource_dir().join("snippets")].iter() {
+            let entries = match fs::read_dir(dir) {
+                Ok(entries) => entries,
+                Err(_) => continue,
+            };
+            // Sorted so a seed picks the same snippet every time.
+            let mut paths: Vec<_> = entries.filter_map(|e| e.ok()).map(|e| e.path()).collect();
+            paths.sort();
+            for path in paths {
+                match fs::read_to_string(&path) {
+                    Ok(contents) => snippets.extend(
+                        split_snippets(&contents).into_iter()
+                            .map(|code| Snippet { language: language(&path), code })
+                    ),
+                    Err(e) => failures.push(format!("failed to read snippets from {}: {}", path.display(), e)),
+                }
+            }
+        }
+        let mut source = CodeSource::new(snippets);
+        if !failures.is_empty() {
+            source.warning = Some(failures.join(", "));
+        }
+        source
+    }
+
+    pub fn new(snippets: Vec<Snippet>) -> CodeSource {
+        CodeSource { snippets, current: None, warning: None, rng: ChaCha8Rng::from_entropy() }
+    }
+
+    pub fn snippets(&self) -> &[Snippet] {
+        &self.snippets
+    }
+}
+
+impl TextSource for CodeSource {
+    /// A random snippet, a different one than last time if possible.
+    fn next_text(&mut self, _statistic: &TypingStatistic) -> String {
+        if self.snippets.is_empty() {
+            return String::new();
+        }
+        let mut next = self.rng.gen_range(0, self.snippets.len());
+        if self.snippets.len() > 1 && Some(next) == self.current {
+            next = (next + 1) % self.snippets.len();
+        }
+        self.current = Some(next);
+        self.snippets[next].code.clone()
+    }
+
+    fn reseed(&mut self, seed: u64) {
+        self.rng = ChaCha8Rng::seed_from_u64(seed);
+        self.current = None;
+    }
+
+    fn label(&self) -> String {
+        let label = match self.current {
+            Some(current) => format!("Code: {}", self.snippets[current].language),
+            None => "Code: no snippets found".to_string(),
+        };
+        match &self.warning {
+            Some(warning) => format!("{}, {}", label, warning),
+            None => label,
+        }
+    }
+}
+
+/// Language of a snippet file, named after its extension.
+fn language(path: &Path) -> String {
+    let extension = path.extension().map(|e| e.to_string_lossy().into_owned()).unwrap_or_default();
+    match extension.as_str() {
+        "rs" => "rust".to_string(),
+        "py" => "python".to_string(),
+        "js" => "javascript".to_string(),
+        "ts" => "typescript".to_string(),
+        "c" | "h" => "c".to_string(),
+        "cpp" | "hpp" => "c++".to_string(),
+        "" => path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default(),
+        _ => extension,
+    }
+}
+
+/// Splits a file at blank lines. A blank line followed by an indented one belongs to the snippet
+/// around it, like the gap between two methods of a class.
+pub fn split_snippets(contents: &str) -> Vec<String> {
+    let mut snippets: Vec<Vec<&str>> = vec![];
+    let mut blank = true;
+    for line in contents.lines().map(|l| l.trim_end()) {
+        if line.is_empty() {
+            blank = true;
+            continue;
+        }
+        let indented = line.starts_with(|c: char| c.is_whitespace());
+        match snippets.last_mut() {
+            Some(snippet) if !blank || indented => {
+                if blank {
+                    snippet.push("");
+                }
+                snippet.push(line);
+            }
+            _ => snippets.push(vec![line]),
+        }
+        blank = false;
+    }
+    snippets.into_iter()
+        .map(|lines| lines.into_iter().take(MAX_SNIPPET_LINES).collect::<Vec<&str>>().join("\n").trim_end().to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blank_lines_separate_snippets() {
+        let contents = "fn one() {}\n\n\nfn two() {\n    2   \n}\n\n";
+        assert_eq!(split_snippets(contents), vec!["fn one() {}", "fn two() {\n    2\n}"]);
+        assert_eq!(split_snippets("\r\na = 1\r\nb = 2\r\n"), vec!["a = 1\nb = 2"]);
+        assert!(split_snippets("\n \n").is_empty());
+    }
+
+    #[test]
+    fn indented_lines_after_a_gap_stay_in_the_snippet() {
+        let contents = "class A:\n    def a(self):\n        pass\n\n\n    def b(self):\n        pass\n\nx = 1";
+        assert_eq!(
+            split_snippets(contents),
+            vec!["class A:\n    def a(self):\n        pass\n\n    def b(self):\n        pass", "x = 1"]
+        );
+    }
+
+    #[test]
+    fn long_snippets_are_cut() {
+        let contents: Vec<String> = (0..MAX_SNIPPET_LINES + 5).map(|i| format!("line {}", i)).collect();
+        let snippets = split_snippets(&contents.join("\n"));
+        assert_eq!(snippets.len(), 1);
+        assert_eq!(snippets[0].lines().count(), MAX_SNIPPET_LINES);
+    }
+
+    #[test]
+    fn snippets_are_not_repeated_back_to_back() {
+        let snippet = |code: &str| Snippet { language: "rust".to_string(), code: code.to_string() };
+        let mut source = CodeSource::new(vec![snippet("a"), snippet("b")]);
+        source.reseed(1);
+        let statistic = TypingStatistic::new();
+        let texts: Vec<String> = (0..10).map(|_| source.next_text(&statistic)).collect();
+        assert!(texts.windows(2).all(|pair| pair[0] != pair[1]), "{:?}", texts);
+        assert_eq!(source.label(), "Code: rust");
+    }
+}
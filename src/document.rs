@@ -0,0 +1,182 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+use crate::storage;
+use crate::typing_session::TextSource;
+use crate::typing_statistic::TypingStatistic;
+
+/// Page of every imported document, keyed by its path.
+const PROGRESS_FILE: &str = "documents.json";
+
+/// Characters per page, about what fits the text block of the window.
+const PAGE_LENGTH: usize = 250;
+
+/// Practices a plain text or Markdown file page by page, remembering the page reached.
+pub struct DocumentSource {
+    key: String,
+    name: String,
+    pages: Vec<String>,
+    page: usize,
+    /// Why the page reached couldn't be saved, shown in the label.
+    warning: Option<String>,
+}
+
+impl DocumentSource {
+    /// Reads the file and resumes at the page reached last time.
+    pub fn open(path: &Path) -> io::Result<DocumentSource> {
+        let contents = fs::read_to_string(path)?;
+        let pages = paginate(&normalize(&contents), PAGE_LENGTH);
+        if pages.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "the file contains no text to type"));
+        }
+
+        let key = fs::canonicalize(path)
+            .unwrap_or_else(|_| path.to_path_buf())
+            .to_string_lossy()
+            .into_owned();
+        let page = load_progress().get(&key).cloned().unwrap_or(0);
+        Ok(DocumentSource {
+            name: path.file_name().map_or(key.clone(), |n| n.to_string_lossy().into_owned()),
+            // A document edited since the last run may have become shorter.
+            page: if page < pages.len() { page } else { 0 },
+            key,
+            pages,
+            warning: None,
+        })
+    }
+
+    pub fn page(&self) -> usize {
+        self.page
+    }
+
+    pub fn page_count(&self) -> usize {
+        self.pages.len()
+    }
+
+    fn save_progress(&self) -> io::Result<()> {
+        let mut progress = load_progress();
+        progress.insert(self.key.clone(), self.page);
+        storage::save(PROGRESS_FILE, &progress)
+    }
+}
+
+impl TextSource for DocumentSource {
+    fn next_text(&mut self, _statistic: &TypingStatistic) -> String {
+        self.pages[self.page].clone()
+    }
+
+    /// Moves on to the next page, the document starts over after the last one.
+    fn text_completed(&mut self) {
+        self.page = (self.page + 1) % self.pages.len();
+        self.warning = self.save_progress()
+            .err()
+            .map(|e| format!("failed to save document progress: {}", e));
+    }
+
+    fn label(&self) -> String {
+        let label = format!("{}: page {}/{}", self.name, self.page + 1, self.pages.len());
+        match &self.warning {
+            Some(warning) => format!("{}, {}", label, warning),
+            None => label,
+        }
+    }
+}
+
+fn load_progress() -> HashMap<String, usize> {
+    storage::load(PROGRESS_FILE).unwrap_or_default()
+}
+
+/// Turns a document into a single line of typeable text: Markdown markup is dropped, typographic
+/// punctuation is replaced by its ASCII counterpart and all whitespace collapses into single spaces.
+/// Letters are composed into the NFC form, marks without a composed letter are kept.
+pub fn normalize(contents: &str) -> String {
+    let mut text = String::new();
+    let mut in_code = false;
+    for line in contents.lines() {
+        let line = line.trim();
+        if line.starts_with("```") {
+            in_code = !in_code;
+            continue;
+        }
+        let line = if in_code { line.to_string() } else { strip_markdown(line) };
+        // Composed first, so accents typed as one key aren't dropped as marks of their own.
+        for c in line.nfc() {
+            match c {
+                '\u{2018}' | '\u{2019}' | '\u{201A}' | '\u{2032}' => text.push('\''),
+                '\u{201C}' | '\u{201D}' | '\u{201E}' | '\u{2033}' | '\u{AB}' | '\u{BB}' => text.push('"'),
+                '\u{2010}'..='\u{2015}' | '\u{2212}' => text.push('-'),
+                '\u{2026}' => text.push_str("..."),
+                c if c.is_whitespace() => text.push(' '),
+                c if c.is_alphanumeric() || c.is_ascii_punctuation() || is_combining_mark(c) => text.push(c),
+                _ => {}
+            }
+        }
+        text.push(' ');
+    }
+    text.split_whitespace().collect::<Vec<&str>>().join(" ")
+}
+
+/// Removes heading, quote and list markers as well as emphasis from a Markdown line.
+fn strip_markdown(line: &str) -> String {
+    let line = line.trim_start_matches(['#', '>']).trim_start();
+    let line = ["- ", "* ", "+ "].iter()
+        .find(|marker| line.starts_with(*marker))
+        .map_or(line, |marker| &line[marker.len()..]);
+    line.replace("**", "").replace("__", "").replace('`', "")
+}
+
+/// Splits `text` at spaces into pages of at most `length` characters. Longer words get a page of their own.
+fn paginate(text: &str, length: usize) -> Vec<String> {
+    let mut pages = vec![];
+    let mut page = String::new();
+    for word in text.split(' ').filter(|w| !w.is_empty()) {
+        if !page.is_empty() && page.chars().count() + 1 + word.chars().count() > length {
+            pages.push(page);
+            page = String::new();
+        }
+        if !page.is_empty() {
+            page.push(' ');
+        }
+        page.push_str(word);
+    }
+    if !page.is_empty() {
+        pages.push(page);
+    }
+    pages
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accents_survive_normalization() {
+        assert_eq!(normalize("cafe\u{301} und Mu\u{308}he"), "caf\u{E9} und M\u{FC}he");
+        assert_eq!(normalize("q\u{301}"), "q\u{301}");
+    }
+
+    #[test]
+    fn markdown_markup_is_dropped() {
+        let contents = "# Title\n\n> A **bold** `quote`\n- item one\n* item two\n\n```\n# not a heading\n```";
+        assert_eq!(normalize(contents), "Title A bold quote item one item two # not a heading");
+    }
+
+    #[test]
+    fn typographic_punctuation_becomes_ascii() {
+        assert_eq!(normalize("\u{201C}Hi\u{201D} \u{2014} it\u{2019}s\u{2026}"), "\"Hi\" - it's...");
+        assert_eq!(normalize("  a\t b\n\n\nc \u{1F600}"), "a b c");
+    }
+
+    #[test]
+    fn pages_break_between_words() {
+        assert_eq!(paginate("aaa bb cc dddddddddd e", 6), vec!["aaa bb", "cc", "dddddddddd", "e"]);
+        // Lengths count characters, not bytes.
+        assert_eq!(paginate("\u{E4}\u{E4} \u{E4}\u{E4}", 5), vec!["\u{E4}\u{E4} \u{E4}\u{E4}"]);
+        assert!(paginate("", 5).is_empty());
+    }
+}
@@ -0,0 +1,78 @@
+use std::io;
+
+use serde::{Deserialize, Serialize};
+
+use crate::storage;
+use crate::typing_statistic::TypingStatistic;
+
+const LESSON_FILE: &str = "lesson.json";
+
+/// Order in which letters are unlocked: the home row first, then the keys closest to it.
+const LETTER_ORDER: &str = "fjdkslaghrueitywoqpvmbncxz";
+const INITIAL_LETTERS: usize = 7;
+
+/// Presses of a letter required before its speed and accuracy are judged.
+const MIN_PRESSES: usize = 30;
+/// Mean time per key, in milliseconds, every unlocked letter has to beat.
+const TARGET_KEY_TIME: f64 = 400.0;
+/// Share of wrong presses every unlocked letter has to stay under.
+const TARGET_ERROR_RATE: f64 = 0.05;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Lesson {
+    unlocked: usize,
+}
+
+impl Default for Lesson {
+    fn default() -> Self {
+        Lesson { unlocked: INITIAL_LETTERS }
+    }
+}
+
+impl Lesson {
+    /// Restores the progress of the previous run or starts from the home row.
+    pub fn load() -> Lesson {
+        storage::load(LESSON_FILE).unwrap_or_default()
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        storage::save(LESSON_FILE, self)
+    }
+
+    /// Letters the user is allowed to practice, in the order they were unlocked.
+    pub fn letters(&self) -> Vec<char> {
+        LETTER_ORDER.chars().take(self.unlocked).collect()
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.unlocked >= LETTER_ORDER.chars().count()
+    }
+
+    /// Unlocks the next letter once every unlocked one is typed fast and accurately enough.
+    /// Returns the newly unlocked letter.
+    pub fn update(&mut self, statistic: &TypingStatistic) -> Option<char> {
+        if self.is_complete() {
+            return None;
+        }
+
+        let mastered = self.letters().iter().all(|key| {
+            let performance = statistic.key_performance(&key.to_string());
+            performance.presses >= MIN_PRESSES
+                && performance.mean_time <= TARGET_KEY_TIME
+                && performance.error_rate() <= TARGET_ERROR_RATE
+        });
+        if !mastered {
+            return None;
+        }
+
+        self.unlocked += 1;
+        LETTER_ORDER.chars().nth(self.unlocked - 1)
+    }
+
+    pub fn label(&self) -> String {
+        if self.is_complete() {
+            return "Lesson: all letters unlocked".to_string();
+        }
+        format!("Lesson: {}", self.letters().iter().collect::<String>())
+    }
+}
@@ -0,0 +1,17 @@
+extern crate serde;
+extern crate serde_json;
+
+pub mod augmentation;
+pub mod code_source;
+pub mod document;
+pub mod lesson;
+pub mod markov;
+pub mod options;
+pub mod passage_source;
+pub mod settings;
+pub mod storage;
+pub mod text_generator;
+pub mod typing_session;
+pub mod typing_statistic;
+pub mod word_list;
+pub mod word_source;
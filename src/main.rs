@@ -1,12 +1,13 @@
 extern crate orbtk;
 extern crate dces;
-extern crate serde;
-extern crate serde_json;
 use std::cell::{Cell, RefCell};
+use std::env;
+use std::rc::Rc;
+use std::time::Instant;
 
 use orbtk::{
     prelude::*,
-    shell::KeyEvent,
+    shell::{Key, KeyEvent},
 };
 
 //mod attributed_text_layout;
@@ -14,145 +15,412 @@ mod attributed_text;
 use crate::attributed_text::*;
 use crate::attributed_text::attributed_text::*;
 
-mod text_generator;
-use text_generator::TextGenerator;
-
-mod typing_statistic;
-use typing_statistic::*;
+use rtyping::document::DocumentSource;
+use rtyping::options::Options;
+use rtyping::settings::{personal_best_text, visible, Settings};
+use rtyping::text_generator::TextGenerator;
+use rtyping::typing_session::*;
+use rtyping::typing_statistic::*;
+use rtyping::word_list::{self, WordList};
+use rtyping::word_source::WordSource;
 
 use crate::attributed_text_block::*;
+use crate::attributed_text_renderer::CARET_ANIMATION;
 
-#[derive(Debug, Copy, Clone)]
-enum Action {
-    KeyPressed(char)
-}
-
-#[derive(Debug, Copy, Clone, PartialEq)]
-enum Pressed {
-    Pressed,
-    NotPressed,
-    WrongPressed
-}
+/// Keys listed on the results of a test.
+const RESULT_KEYS: usize = 8;
+/// Substitutions listed on the results of a test.
+const RESULT_SUBSTITUTIONS: usize = 5;
 
-impl Default for Pressed{
-    fn default() -> Pressed {
-        Pressed::NotPressed
-    }
-}
+/// Shade behind wrong letters, so they stand out without telling red from green.
+const WRONG_BACKGROUND: &str = "#641E16";
 
-#[derive(Default, Copy, Clone)]
-struct KeyLetter{
-    character: char,
-    pressed: Pressed
-}
-
-impl KeyLetter{
-    pub fn new(character: char, pressed: Pressed) -> KeyLetter{
-        KeyLetter{character, pressed}
-    }
+#[derive(Debug, Clone)]
+enum Action {
+    /// Text of a keystroke, several letters when composed by a dead key or an input method.
+    KeyPressed(String),
+    Backspace,
+    DeleteWord,
+    Restart,
+    Retry,
+    ToggleErrorPolicy,
+    CycleGeneration,
+    ToggleLesson,
+    CycleTestMode,
+    CycleWordList,
+    CycleWordLimit,
+    ToggleCapitals,
+    TogglePunctuation,
+    ToggleNumbers,
+    CycleContent,
+    CycleCaret,
+    ToggleWordHighlight,
+    ToggleSmoothCaret,
+    CycleTypedDisplay
 }
 
 pub struct MainViewState {
-    text_gen: TextGenerator,
-    statistic: RefCell<TypingStatistic>,
-    cursor: Cell<usize>,
-    text: RefCell<Vec<KeyLetter>>,
+    session: RefCell<TypingSession>,
+    words: Rc<RefCell<WordSource>>,
+    settings: RefCell<Settings>,
+    control: Cell<bool>,
     action: Cell<Option<Action>>,
+    /// When the text was last redrawn, the caret may still be gliding to its letter.
+    redrawn: Cell<Instant>,
+    /// Redraws requested from the text, see `RedrawRequest`.
+    redraw_requests: Cell<u64>,
 }
 
 impl Default for MainViewState {
     fn default() -> Self {
-        let st = MainViewState {
-            text_gen: TextGenerator::new(include_str!("../res/words_filtered.txt")),
-            statistic: RefCell::new(TypingStatistic::new()),
-            cursor: Cell::new(0),
-            text: RefCell::new(vec![]),
-            action: Cell::new(None),
+        let options = Options::parse(env::args().skip(1)).unwrap_or_else(|e| {
+            println!("{}", e);
+            Options::default()
+        });
+        let settings = Settings::load();
+        let mut words = WordSource::new(load_words(&settings), settings.lesson(), 20);
+        words.set_mode(settings.generation, settings.adaptive_strength);
+        words.set_augmentation(settings.augmentation);
+        let words = Rc::new(RefCell::new(words));
+
+        // A file given on the command line is practiced instead of random words.
+        let document = options.document.and_then(|path| match DocumentSource::open(&path) {
+            Ok(document) => Some(document),
+            Err(e) => {
+                println!("failed to open {}: {}", path.display(), e);
+                None
+            }
+        });
+        let source: Box<dyn TextSource> = match document {
+            Some(document) => Box::new(document),
+            None => settings.content.source(&words),
         };
-        st.generate_text();
-        st
+
+        let mut session = TypingSession::new(
+            source,
+            Box::new(SystemClock),
+            TypingStatistic::load(),
+        );
+        session.set_error_policy(settings.error_policy);
+        session.set_mode(settings.test_mode);
+        if options.seed.is_some() {
+            session.set_seed(options.seed);
+        }
+
+        MainViewState {
+            session: RefCell::new(session),
+            words,
+            settings: RefCell::new(settings),
+            control: Cell::new(false),
+            action: Cell::new(None),
+            redrawn: Cell::new(Instant::now()),
+            redraw_requests: Cell::new(0),
+        }
     }
 }
 
 impl MainViewState {
-    fn generate_text(&self){
-        self.text.replace(self.text_gen.generate(&vec!['1'], 20).join(" ")
-            .chars().map(|c| KeyLetter::new(c, Pressed::NotPressed)).collect());
-    }
     fn action(&self, action: impl Into<Option<Action>>) {
         self.action.set(action.into());
     }
+    fn error_policy_text(&self) -> String{
+        format!("Errors: {}", self.settings.borrow().error_policy.description())
+    }
+    fn generation_text(&self) -> String{
+        format!("Text: {}", self.settings.borrow().generation.description())
+    }
+    fn lesson_text(&self) -> String{
+        self.augmentation_text("Lesson", self.settings.borrow().lesson)
+    }
+    fn test_mode_text(&self) -> String{
+        format!("Mode: {}", self.settings.borrow().test_mode.description())
+    }
+    fn word_list_text(&self) -> String{
+        format!("Words: {}", self.settings.borrow().word_list)
+    }
+    fn word_limit_text(&self) -> String{
+        word_list::limit_description(self.settings.borrow().word_limit)
+    }
+    fn augmentation_text(&self, name: &str, enabled: bool) -> String{
+        format!("{}: {}", name, if enabled {"on"} else {"off"})
+    }
+    fn content_text(&self) -> String{
+        format!("Practice: {}", self.settings.borrow().content.description())
+    }
+    fn capitals_text(&self) -> String{
+        self.augmentation_text("Capitals", self.settings.borrow().augmentation.capitals)
+    }
+    fn punctuation_text(&self) -> String{
+        self.augmentation_text("Punctuation", self.settings.borrow().augmentation.punctuation)
+    }
+    fn numbers_text(&self) -> String{
+        self.augmentation_text("Numbers", self.settings.borrow().augmentation.numbers)
+    }
+    fn caret_text(&self) -> String{
+        format!("Caret: {}", self.settings.borrow().caret_style.description())
+    }
+    fn word_highlight_text(&self) -> String{
+        self.augmentation_text("Word highlight", self.settings.borrow().highlight_word)
+    }
+    fn smooth_caret_text(&self) -> String{
+        self.augmentation_text("Smooth caret", self.settings.borrow().smooth_caret)
+    }
+    fn typed_display_text(&self) -> String{
+        format!("Mistyped: {}", self.settings.borrow().typed_display.description())
+    }
+    fn timer_text(&self) -> String{
+        let session = self.session.borrow();
+        match session.remaining() {
+            Some(_) if session.is_locked() => "Time is up".to_string(),
+            Some(remaining) => format!("Time left: {:.1} s", remaining.as_secs_f64()),
+            None => String::new()
+        }
+    }
+    fn result_text(&self) -> String{
+        let session = self.session.borrow();
+        let result = session.current_state();
+        format!(
+            "Result: {:.1} wpm, net {:.1} wpm, accuracy {:.1}%, seed {}",
+            result.gross_wpm, result.net_wpm, result.accuracy, session.seed()
+        )
+    }
+    /// Source of a finished passage and how the test compares to earlier runs on it.
+    fn result_passage_text(&self) -> String{
+        let session = self.session.borrow();
+        let mut lines = vec![];
+        if let Some(attribution) = session.attribution() {
+            lines.push(format!("From {}", attribution));
+        }
+        if let Some(best) = session.statistic().last_sample_best() {
+            lines.push(personal_best_text(&best));
+        }
+        lines.join(". ")
+    }
+    /// Slowest keys of the finished test.
+    fn result_keys_text(&self) -> String{
+        let keys: Vec<String> = self.session.borrow().statistic().last_sample_keys().into_iter()
+            .filter(|(key, _)| key != " ")
+            .take(RESULT_KEYS)
+            .map(|(key, performance)| format!(
+                "{} {:.0} ms ({} of {} wrong)",
+                key, performance.mean_time, performance.errors, performance.presses + performance.errors
+            ))
+            .collect();
+        format!("Slowest keys: {}", keys.join(", "))
+    }
+    /// Letters most often typed in place of others, over every test so far.
+    fn result_substitutions_text(&self) -> String{
+        let substitutions: Vec<String> = self.session.borrow().statistic()
+            .common_substitutions(RESULT_SUBSTITUTIONS).into_iter()
+            .map(|s| format!("{} \u{2192} {} ({}\u{D7})", visible(&s.expected), visible(&s.typed), s.count))
+            .collect();
+        if substitutions.is_empty() {
+            return String::new();
+        }
+        format!("Common mistakes: {}", substitutions.join(", "))
+    }
+    fn change_settings(&self, action: Action, context: &mut Context<'_>){
+        let mut settings = self.settings.borrow_mut();
+        match action {
+            Action::ToggleErrorPolicy => {
+                settings.error_policy = settings.error_policy.toggled();
+                self.session.borrow_mut().set_error_policy(settings.error_policy);
+            }
+            Action::CycleGeneration => {
+                settings.generation = settings.generation.next();
+                self.words.borrow_mut().set_mode(settings.generation, settings.adaptive_strength);
+            }
+            Action::ToggleLesson => {
+                settings.lesson = !settings.lesson;
+                self.words.borrow_mut().set_lesson(settings.lesson());
+                self.session.borrow_mut().restart();
+            }
+            Action::CycleTestMode => {
+                settings.test_mode = settings.test_mode.next();
+                self.session.borrow_mut().set_mode(settings.test_mode);
+            }
+            Action::CycleWordList => {
+                if let Some(name) = WordList::next_name(&settings.word_list) {
+                    settings.word_list = name;
+                }
+                self.words.borrow_mut().set_generator(load_words(&settings));
+                self.session.borrow_mut().restart();
+            }
+            Action::CycleWordLimit => {
+                settings.word_limit = word_list::next_limit(settings.word_limit);
+                self.words.borrow_mut().set_generator(load_words(&settings));
+                self.session.borrow_mut().restart();
+            }
+            Action::ToggleCapitals | Action::TogglePunctuation | Action::ToggleNumbers => {
+                let augmentation = &mut settings.augmentation;
+                match action {
+                    Action::ToggleCapitals => augmentation.capitals = !augmentation.capitals,
+                    Action::TogglePunctuation => augmentation.punctuation = !augmentation.punctuation,
+                    _ => augmentation.numbers = !augmentation.numbers,
+                }
+                self.words.borrow_mut().set_augmentation(settings.augmentation);
+                self.session.borrow_mut().restart();
+            }
+            Action::CycleContent => {
+                settings.content = settings.content.next();
+                self.session.borrow_mut().set_source(settings.content.source(&self.words));
+            }
+            Action::CycleCaret => settings.caret_style = settings.caret_style.next(),
+            Action::ToggleWordHighlight => settings.highlight_word = !settings.highlight_word,
+            Action::ToggleSmoothCaret => settings.smooth_caret = !settings.smooth_caret,
+            Action::CycleTypedDisplay => settings.typed_display = settings.typed_display.next(),
+            _ => {}
+        }
+        if let Err(e) = settings.save() {
+            println!("failed to save settings: {}", e);
+        }
+        drop(settings);
+
+        set_text(context, "error_policy", self.error_policy_text());
+        set_text(context, "generation", self.generation_text());
+        set_text(context, "lesson_toggle", self.lesson_text());
+        set_text(context, "test_mode", self.test_mode_text());
+        set_text(context, "word_list", self.word_list_text());
+        set_text(context, "word_limit", self.word_limit_text());
+        set_text(context, "content", self.content_text());
+        set_text(context, "capitals", self.capitals_text());
+        set_text(context, "punctuation", self.punctuation_text());
+        set_text(context, "numbers", self.numbers_text());
+        set_text(context, "caret", self.caret_text());
+        set_text(context, "word_highlight", self.word_highlight_text());
+        set_text(context, "smooth_caret", self.smooth_caret_text());
+        set_text(context, "typed_display", self.typed_display_text());
+        set_text(context, "lesson", self.session.borrow().label());
+
+        let settings = self.settings.borrow();
+        let mut main_text = context.child_by_id("main_text").unwrap();
+        main_text.set(Caret(settings.caret_style));
+        main_text.set(HighlightWord(settings.highlight_word));
+        main_text.set(SmoothCaret(settings.smooth_caret));
+        main_text.set(ShowTyped(settings.typed_display));
+        main_text.set(SpaceSymbol(settings.content.space_symbol().to_string()));
+    }
+    /// Wrong letters are underlined until corrected, the ones skipped over are struck out.
     fn get_styled_text(&self) -> Vec<AttributedLetter>{
-        self.text.clone().into_inner().iter()
-            .map(|kl| AttributedLetter::new(
-                if kl.character == ' ' {'_'} else {kl.character},
-                (match kl.pressed {
-                    Pressed::Pressed => "#239B56",
-                    Pressed::NotPressed => "#E5E7E9",
-                    Pressed::WrongPressed => "#E74C3C"
-                }).into())
-            ).collect()
+        let session = self.session.borrow();
+        let cursor = session.cursor();
+        session.text().iter().enumerate()
+            .map(|(i, kl)| {
+                let mut letter = AttributedLetter::new(kl.character.clone(), kl.pressed.color().into());
+                letter.typed = kl.typed.clone();
+                if kl.pressed == Pressed::WrongPressed {
+                    letter.background = Some(WRONG_BACKGROUND.into());
+                    letter.weight = FontWeight::Bold;
+                    if i < cursor {
+                        letter.strikethrough = true;
+                    } else {
+                        letter.underline = true;
+                    }
+                }
+                letter
+            }).collect()
     }
 }
 
 impl State for MainViewState {
     fn update(&self, context: &mut Context<'_>) {
-        if let Some(action) = self.action.get() {
-            match action {
-                Action::KeyPressed(key) => {
-                    let mut statistic = self.statistic.borrow_mut();
-                    if statistic.is_finished(){
-                        statistic.start_sample();
-                    }
-
-                    let cursor = self.cursor.get();
-                    let text = self.text.clone().into_inner().clone();
-                    let actual_char = text.get(cursor).unwrap_or(&KeyLetter::default()).character.clone();
-                    let correct = actual_char == key;
-
-                    if cursor >= text.len(){
-                        self.generate_text();
-                        self.cursor.set(0);
-                        statistic.finish_sample();
-                    } else{
-                        if !correct{
-                            if text[cursor].pressed == Pressed::NotPressed {
-                                statistic.key_pressed(key, false);
-                            }
-                        }
-
-                        self.text.borrow_mut()[cursor] =
-                            KeyLetter::new(actual_char, if correct { Pressed::Pressed} else {Pressed::WrongPressed});
-
-                        if correct {
-                            self.cursor.set(cursor + 1);
-                            statistic.key_pressed(key, true);
-                        }
-                    }
+        let action = self.action.take();
 
-                    context.child_by_id("main_text").unwrap().set(AttributedText(self.get_styled_text()));
+        let outcome = match action {
+            Some(Action::KeyPressed(text)) => self.session.borrow_mut().press(&text),
+            Some(Action::Backspace) => self.session.borrow_mut().backspace(),
+            Some(Action::DeleteWord) => self.session.borrow_mut().delete_word(),
+            Some(Action::Restart) => {
+                self.session.borrow_mut().restart();
+                Outcome::Ignored
+            }
+            Some(Action::Retry) => {
+                self.session.borrow_mut().retry();
+                Outcome::Ignored
+            }
+            Some(action) => {
+                self.change_settings(action, context);
+                Outcome::Ignored
+            }
+            None => self.session.borrow_mut().tick(),
+        };
 
-                    let current_stat = statistic.get_current_state();
-                    context
-                        .child_by_id("speed")
-                        .unwrap()
-                        .get_mut::<Text>()
-                        .0 = String16::from(format!("Speed: {:.1} cpm", current_stat.speed.min(1000.0)));
+        if outcome == Outcome::Finished {
+            let session = self.session.borrow();
+            if let Err(e) = session.statistic().save() {
+                println!("failed to save statistic: {}", e);
+            }
+            set_text(context, "lesson", session.label());
+        }
 
-                    context
-                        .child_by_id("errors")
-                        .unwrap()
-                        .get_mut::<Text>()
-                        .0 = String16::from(format!("Error: {}",  current_stat.errors));
-                }
+        let refresh = action.is_some() || outcome == Outcome::Finished;
+        if refresh || self.session.borrow().remaining().is_some() {
+            set_text(context, "timer", self.timer_text());
+        }
+        if !refresh {
+            // Keeps redrawing until the caret has glided to its letter.
+            if self.settings.borrow().smooth_caret && self.redrawn.get().elapsed() < CARET_ANIMATION {
+                self.redraw_requests.set(self.redraw_requests.get() + 1);
+                context.child_by_id("main_text").unwrap().set(RedrawRequest(self.redraw_requests.get()));
             }
+            return;
+        }
+
+        let current_stat = self.session.borrow().current_state();
+        context.child_by_id("main_text").unwrap().set(AttributedText(self.get_styled_text()));
+        context.child_by_id("main_text").unwrap().set(CaretPosition(self.session.borrow().cursor()));
+        self.redrawn.set(Instant::now());
 
-            self.action.set(None);
+        // The results of a finished test take the place of the text until the next one starts.
+        let locked = self.session.borrow().is_locked();
+        let (text_visibility, results_visibility) = if locked {
+            (VisibilityValue::Collapsed, VisibilityValue::Visible)
+        } else {
+            (VisibilityValue::Visible, VisibilityValue::Collapsed)
+        };
+        context.child_by_id("main_text").unwrap().set(Visibility(text_visibility));
+        context.child_by_id("results").unwrap().set(Visibility(results_visibility));
+        if locked {
+            set_text(context, "result_summary", self.result_text());
+            set_text(context, "result_passage", self.result_passage_text());
+            set_text(context, "result_keys", self.result_keys_text());
+            set_text(context, "result_substitutions", self.result_substitutions_text());
         }
+
+        set_text(context, "speed", format!(
+            "Speed: {:.1} cpm, {:.1} wpm, net {:.1} wpm",
+            current_stat.speed, current_stat.gross_wpm, current_stat.net_wpm
+        ));
+        set_text(context, "accuracy", format!(
+            "Accuracy: {:.1}%, consistency: {:.1}%",
+            current_stat.accuracy, current_stat.consistency
+        ));
+        set_text(context, "errors", format!(
+            "Error: {}, corrections: {}",
+            current_stat.errors, current_stat.corrections
+        ));
     }
 }
+
+fn load_words(settings: &Settings) -> TextGenerator {
+    let mut warning = None;
+    let generator = settings.load_words(&mut warning);
+    if let Some(warning) = warning {
+        println!("{}", warning);
+    }
+    generator
+}
+
+fn set_text(context: &mut Context<'_>, id: &str, text: String) {
+    context
+        .child_by_id(id)
+        .unwrap()
+        .get_mut::<Text>()
+        .0 = String16::from(text);
+}
+
 widget!(
-    MainView<MainViewState>: KeyDownHandler {
+    MainView<MainViewState>: KeyDownHandler, KeyUpHandler {
         text: AttributedText
     }
 );
@@ -160,9 +428,194 @@ widget!(
 impl Template for MainView {
     fn template(self, id: Entity, context: &mut BuildContext) -> Self {
         let state = self.clone_state();
+        let policy_state = state.clone();
+        let generation_state = state.clone();
+        let lesson_state = state.clone();
+        let test_mode_state = state.clone();
+        let word_list_state = state.clone();
+        let word_limit_state = state.clone();
+        let capitals_state = state.clone();
+        let punctuation_state = state.clone();
+        let numbers_state = state.clone();
+        let content_state = state.clone();
+        let caret_state = state.clone();
+        let word_highlight_state = state.clone();
+        let smooth_caret_state = state.clone();
+        let typed_display_state = state.clone();
+        let retry_state = state.clone();
+        let next_state = state.clone();
+        let key_up_state = state.clone();
         self.name("MainView").text(state.get_styled_text()).child(
                     Stack::create()
                         .margin((10.0, 10.0, 10.0, 10.0))
+                        .child(
+                            Stack::create()
+                                .orientation(OrientationValue::Horizontal)
+                                .child(
+                                    Button::create()
+                                        .selector(SelectorValue::new().id("error_policy"))
+                                        .text(state.error_policy_text())
+                                        .on_click(move |_| {
+                                            policy_state.action(Action::ToggleErrorPolicy);
+                                            true
+                                        })
+                                        .build(context),
+                                )
+                                .child(
+                                    Button::create()
+                                        .selector(SelectorValue::new().id("generation"))
+                                        .text(state.generation_text())
+                                        .margin((8.0, 0.0, 0.0, 0.0))
+                                        .on_click(move |_| {
+                                            generation_state.action(Action::CycleGeneration);
+                                            true
+                                        })
+                                        .build(context),
+                                )
+                                .child(
+                                    Button::create()
+                                        .selector(SelectorValue::new().id("lesson_toggle"))
+                                        .text(state.lesson_text())
+                                        .margin((8.0, 0.0, 0.0, 0.0))
+                                        .on_click(move |_| {
+                                            lesson_state.action(Action::ToggleLesson);
+                                            true
+                                        })
+                                        .build(context),
+                                )
+                                .child(
+                                    Button::create()
+                                        .selector(SelectorValue::new().id("test_mode"))
+                                        .text(state.test_mode_text())
+                                        .margin((8.0, 0.0, 0.0, 0.0))
+                                        .on_click(move |_| {
+                                            test_mode_state.action(Action::CycleTestMode);
+                                            true
+                                        })
+                                        .build(context),
+                                )
+                                .child(
+                                    Button::create()
+                                        .selector(SelectorValue::new().id("word_list"))
+                                        .text(state.word_list_text())
+                                        .margin((8.0, 0.0, 0.0, 0.0))
+                                        .on_click(move |_| {
+                                            word_list_state.action(Action::CycleWordList);
+                                            true
+                                        })
+                                        .build(context),
+                                )
+                                .child(
+                                    Button::create()
+                                        .selector(SelectorValue::new().id("word_limit"))
+                                        .text(state.word_limit_text())
+                                        .margin((8.0, 0.0, 0.0, 0.0))
+                                        .on_click(move |_| {
+                                            word_limit_state.action(Action::CycleWordLimit);
+                                            true
+                                        })
+                                        .build(context),
+                                )
+                                .build(context),
+                        )
+                        .child(
+                            Stack::create()
+                                .orientation(OrientationValue::Horizontal)
+                                .margin((0.0, 8.0, 0.0, 0.0))
+                                .child(
+                                    Button::create()
+                                        .selector(SelectorValue::new().id("content"))
+                                        .text(state.content_text())
+                                        .on_click(move |_| {
+                                            content_state.action(Action::CycleContent);
+                                            true
+                                        })
+                                        .build(context),
+                                )
+                                .child(
+                                    Button::create()
+                                        .selector(SelectorValue::new().id("capitals"))
+                                        .text(state.capitals_text())
+                                        .margin((8.0, 0.0, 0.0, 0.0))
+                                        .on_click(move |_| {
+                                            capitals_state.action(Action::ToggleCapitals);
+                                            true
+                                        })
+                                        .build(context),
+                                )
+                                .child(
+                                    Button::create()
+                                        .selector(SelectorValue::new().id("punctuation"))
+                                        .text(state.punctuation_text())
+                                        .margin((8.0, 0.0, 0.0, 0.0))
+                                        .on_click(move |_| {
+                                            punctuation_state.action(Action::TogglePunctuation);
+                                            true
+                                        })
+                                        .build(context),
+                                )
+                                .child(
+                                    Button::create()
+                                        .selector(SelectorValue::new().id("numbers"))
+                                        .text(state.numbers_text())
+                                        .margin((8.0, 0.0, 0.0, 0.0))
+                                        .on_click(move |_| {
+                                            numbers_state.action(Action::ToggleNumbers);
+                                            true
+                                        })
+                                        .build(context),
+                                )
+                                .build(context),
+                        )
+                        .child(
+                            Stack::create()
+                                .orientation(OrientationValue::Horizontal)
+                                .margin((0.0, 8.0, 0.0, 0.0))
+                                .child(
+                                    Button::create()
+                                        .selector(SelectorValue::new().id("caret"))
+                                        .text(state.caret_text())
+                                        .on_click(move |_| {
+                                            caret_state.action(Action::CycleCaret);
+                                            true
+                                        })
+                                        .build(context),
+                                )
+                                .child(
+                                    Button::create()
+                                        .selector(SelectorValue::new().id("word_highlight"))
+                                        .text(state.word_highlight_text())
+                                        .margin((8.0, 0.0, 0.0, 0.0))
+                                        .on_click(move |_| {
+                                            word_highlight_state.action(Action::ToggleWordHighlight);
+                                            true
+                                        })
+                                        .build(context),
+                                )
+                                .child(
+                                    Button::create()
+                                        .selector(SelectorValue::new().id("smooth_caret"))
+                                        .text(state.smooth_caret_text())
+                                        .margin((8.0, 0.0, 0.0, 0.0))
+                                        .on_click(move |_| {
+                                            smooth_caret_state.action(Action::ToggleSmoothCaret);
+                                            true
+                                        })
+                                        .build(context),
+                                )
+                                .child(
+                                    Button::create()
+                                        .selector(SelectorValue::new().id("typed_display"))
+                                        .text(state.typed_display_text())
+                                        .margin((8.0, 0.0, 0.0, 0.0))
+                                        .on_click(move |_| {
+                                            typed_display_state.action(Action::CycleTypedDisplay);
+                                            true
+                                        })
+                                        .build(context),
+                                )
+                                .build(context),
+                        )
                         .child(
                             TextBlock::create()
                                 .selector(SelectorValue::new().id("speed"))
@@ -170,6 +623,13 @@ impl Template for MainView {
                                 .margin((0.0, 8.0, 0.0, 0.0))
                                 .build(context),
                         )
+                        .child(
+                            TextBlock::create()
+                                .selector(SelectorValue::new().id("accuracy"))
+                                .text("Accuracy: 100%")
+                                .margin((0.0, 8.0, 0.0, 0.0))
+                                .build(context),
+                        )
                         .child(
                             TextBlock::create()
                                 .selector(SelectorValue::new().id("errors"))
@@ -177,17 +637,116 @@ impl Template for MainView {
                                 .margin((0.0, 8.0, 0.0, 0.0))
                                 .build(context),
                         )
+                        .child(
+                            TextBlock::create()
+                                .selector(SelectorValue::new().id("timer"))
+                                .text(state.timer_text())
+                                .margin((0.0, 8.0, 0.0, 0.0))
+                                .build(context),
+                        )
+                        .child(
+                            TextBlock::create()
+                                .selector(SelectorValue::new().id("lesson"))
+                                .text(state.session.borrow().label())
+                                .margin((0.0, 8.0, 0.0, 0.0))
+                                .build(context),
+                        )
                         .child(
                             AttributedTextBlock::create()
                                 .selector(SelectorValue::new().id("main_text"))
                                 .text(id)
+                                .caret(state.settings.borrow().caret_style)
+                                .highlight_word(state.settings.borrow().highlight_word)
+                                .smooth_caret(state.settings.borrow().smooth_caret)
+                                .show_typed(state.settings.borrow().typed_display)
+                                .space_symbol(state.settings.borrow().content.space_symbol().to_string())
                                 .font_size(20.0)
                                 .margin((0.0, 8.0, 0.0, 0.0))
                                 .build(context),
                         )
+                        .child(
+                            Stack::create()
+                                .selector(SelectorValue::new().id("results"))
+                                .visibility(VisibilityValue::Collapsed)
+                                .margin((0.0, 8.0, 0.0, 0.0))
+                                .child(
+                                    TextBlock::create()
+                                        .selector(SelectorValue::new().id("result_summary"))
+                                        .text("")
+                                        .build(context),
+                                )
+                                .child(
+                                    TextBlock::create()
+                                        .selector(SelectorValue::new().id("result_passage"))
+                                        .text("")
+                                        .margin((0.0, 8.0, 0.0, 0.0))
+                                        .build(context),
+                                )
+                                .child(
+                                    TextBlock::create()
+                                        .selector(SelectorValue::new().id("result_keys"))
+                                        .text("")
+                                        .margin((0.0, 8.0, 0.0, 0.0))
+                                        .build(context),
+                                )
+                                .child(
+                                    TextBlock::create()
+                                        .selector(SelectorValue::new().id("result_substitutions"))
+                                        .text("")
+                                        .margin((0.0, 8.0, 0.0, 0.0))
+                                        .build(context),
+                                )
+                                .child(
+                                    Stack::create()
+                                        .orientation(OrientationValue::Horizontal)
+                                        .margin((0.0, 8.0, 0.0, 0.0))
+                                        .child(
+                                            Button::create()
+                                                .text("Retry")
+                                                .on_click(move |_| {
+                                                    retry_state.action(Action::Retry);
+                                                    true
+                                                })
+                                                .build(context),
+                                        )
+                                        .child(
+                                            Button::create()
+                                                .text("Next")
+                                                .margin((8.0, 0.0, 0.0, 0.0))
+                                                .on_click(move |_| {
+                                                    next_state.action(Action::Restart);
+                                                    true
+                                                })
+                                                .build(context),
+                                        )
+                                        .build(context),
+                                )
+                                .build(context),
+                        )
                         .build(context)
         ).on_key_down(move |event: KeyEvent| -> bool {
-            state.action(Action::KeyPressed(event.text.chars().next().unwrap_or_default()));
+            match event.key {
+                Key::Control => state.control.set(true),
+                Key::Backspace if state.control.get() => state.action(Action::DeleteWord),
+                Key::Backspace => state.action(Action::Backspace),
+                Key::Escape => state.action(Action::Restart),
+                Key::Enter => state.action(Action::KeyPressed("\n".to_string())),
+                Key::Tab => state.action(Action::KeyPressed("\t".to_string())),
+                Key::Delete => {}
+                _ => {
+                    // Modifier keys and control shortcuts don't produce anything to type, dead keys
+                    // and input methods may produce several letters at once.
+                    let text = event.text;
+                    if !text.is_empty() && !state.control.get() && !text.chars().any(char::is_control) {
+                        state.action(Action::KeyPressed(text));
+                    }
+                }
+            }
+            true
+        }).on_key_up(move |event: KeyEvent| -> bool {
+            if event.key == Key::Control {
+                key_up_state.control.set(false);
+            }
             true
         })
     }
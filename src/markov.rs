@@ -0,0 +1,224 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::storage;
+use crate::text_generator::GeneratorError;
+
+/// Corpus the word-level model is trained on, looked up in the config directory, then in `res/markov`.
+const CORPUS_FILE: &str = "corpus.txt";
+
+/// Letters a character-level state is made of, 3 keeps the pseudo-words pronounceable without
+/// copying the word list.
+const CHARACTER_ORDER: usize = 3;
+/// Words a word-level state is made of, a small corpus would be repeated verbatim with more.
+const WORD_ORDER: usize = 1;
+
+/// Pseudo-words longer than this are thrown away.
+const MAX_WORD_LENGTH: usize = 12;
+/// Steps tried per requested word before giving up on a letter set.
+const STEPS_PER_WORD: usize = 100;
+
+/// Pads the beginning of a sequence and marks its end.
+const START: &str = "\u{2}";
+const END: &str = "\u{3}";
+/// Joins the tokens of a state.
+const SEPARATOR: char = '\u{1F}';
+
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub enum MarkovLevel {
+    /// Pronounceable pseudo-words built letter by letter.
+    Characters,
+    /// Phrases built word by word.
+    Words,
+}
+
+impl MarkovLevel {
+    pub fn description(&self) -> &'static str {
+        match self {
+            MarkovLevel::Characters => "letters",
+            MarkovLevel::Words => "words"
+        }
+    }
+}
+
+/// Generates text following the transitions observed in a training corpus.
+pub struct MarkovGenerator {
+    level: MarkovLevel,
+    order: usize,
+    /// Tokens seen after every state, with how often.
+    transitions: HashMap<String, Vec<(String, usize)>>,
+}
+
+impl MarkovGenerator {
+    /// Learns letter transitions from a word list.
+    pub fn characters(words: &[String]) -> MarkovGenerator {
+        MarkovGenerator::train(
+            MarkovLevel::Characters,
+            CHARACTER_ORDER,
+            words.iter().map(|w| w.chars().map(|c| c.to_string()).collect()),
+        )
+    }
+
+    /// Learns word transitions from running text, every sentence is a sequence of its own.
+    pub fn words(corpus: &str) -> MarkovGenerator {
+        let sentences = corpus
+            .split(&['.', '?', '!', ';'][..])
+            .map(|sentence| sentence.split_whitespace()
+                .map(|w| w.trim_matches(|c: char| !c.is_alphanumeric()).to_lowercase())
+                .filter(|w| !w.is_empty())
+                .collect::<Vec<String>>())
+            .filter(|sentence| !sentence.is_empty());
+        MarkovGenerator::train(MarkovLevel::Words, WORD_ORDER, sentences)
+    }
+
+    /// Trains the word-level model on the corpus file.
+    pub fn load_words() -> io::Result<MarkovGenerator> {
+        let corpus = fs::read_to_string(storage::config_dir().join(CORPUS_FILE))
+            .or_else(|_| fs::read_to_string(storage::resource_dir().join("markov").join(CORPUS_FILE)))?;
+        Ok(MarkovGenerator::words(&corpus))
+    }
+
+    fn train(level: MarkovLevel, order: usize, sequences: impl Iterator<Item=Vec<String>>) -> MarkovGenerator {
+        let mut counts: HashMap<String, HashMap<String, usize>> = HashMap::new();
+        for sequence in sequences {
+            let mut tokens: Vec<&str> = vec![START; order];
+            for token in sequence.iter().map(|t| t.as_str()).chain(std::iter::once(END)) {
+                let state = state_key(&tokens[tokens.len() - order..]);
+                *counts.entry(state).or_default().entry(token.to_string()).or_insert(0) += 1;
+                tokens.push(token);
+            }
+        }
+
+        MarkovGenerator {
+            level,
+            order,
+            // Sorted, as the order of a hash map would make seeded generation differ between runs.
+            transitions: counts.into_iter()
+                .map(|(state, next)| {
+                    let mut next: Vec<(String, usize)> = next.into_iter().collect();
+                    next.sort();
+                    (state, next)
+                })
+                .collect(),
+        }
+    }
+
+    pub fn level(&self) -> MarkovLevel {
+        self.level
+    }
+
+    /// Generates `len` words made only of `chars`. An empty letter set allows every letter.
+    pub fn generate<R: Rng>(&self, chars: &[char], len: usize, rng: &mut R) -> Result<Vec<String>, GeneratorError> {
+        let allowed = |token: &str| token == END || chars.is_empty() || token.chars().all(|c| chars.contains(&c));
+        let mut text: Vec<String> = vec![];
+        let mut tokens: Vec<String> = vec![START.to_string(); self.order];
+        let mut word = String::new();
+
+        for _ in 0..len * STEPS_PER_WORD {
+            if text.len() >= len {
+                return Ok(text);
+            }
+            let state = state_key(&tokens[tokens.len() - self.order..]);
+            let next = self.transitions.get(&state)
+                .and_then(|next| choose(next.iter().filter(|(t, _)| allowed(t)), rng));
+
+            match (next, self.level) {
+                // A dead end, start over with a new sequence.
+                (None, _) => {
+                    tokens = vec![START.to_string(); self.order];
+                    word.clear();
+                }
+                (Some(END), MarkovLevel::Characters) => {
+                    if !word.is_empty() && word.chars().count() <= MAX_WORD_LENGTH {
+                        text.push(word.clone());
+                    }
+                    tokens = vec![START.to_string(); self.order];
+                    word.clear();
+                }
+                (Some(END), MarkovLevel::Words) => tokens = vec![START.to_string(); self.order],
+                (Some(token), MarkovLevel::Characters) => {
+                    word.push_str(token);
+                    tokens.push(token.to_string());
+                }
+                (Some(token), MarkovLevel::Words) => {
+                    text.push(token.to_string());
+                    tokens.push(token.to_string());
+                }
+            }
+        }
+
+        if text.len() >= len {
+            return Ok(text);
+        }
+        Err(GeneratorError::NotEnoughWords { available: text.len(), required: len })
+    }
+}
+
+fn state_key<S: AsRef<str>>(tokens: &[S]) -> String {
+    tokens.iter().map(|t| t.as_ref()).collect::<Vec<&str>>().join(&SEPARATOR.to_string())
+}
+
+/// Picks one of the tokens proportionally to its count.
+fn choose<'a, R: Rng>(next: impl Iterator<Item=&'a (String, usize)>, rng: &mut R) -> Option<&'a str> {
+    let next: Vec<&(String, usize)> = next.collect();
+    let index = WeightedIndex::new(next.iter().map(|(_, count)| *count)).ok()?;
+    Some(next[index.sample(rng)].0.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    fn words(list: &str) -> Vec<String> {
+        list.split_whitespace().map(|w| w.to_string()).collect()
+    }
+
+    #[test]
+    fn pseudo_words_keep_to_the_letter_set() {
+        let markov = MarkovGenerator::characters(&words("sad lad fall salad flask ask dad glad lass jade"));
+        let letters = ['a', 's', 'd', 'l', 'f', 'k'];
+        let text = markov.generate(&letters, 30, &mut ChaCha8Rng::seed_from_u64(1)).unwrap();
+        assert_eq!(text.len(), 30);
+        assert!(text.iter().all(|w| w.chars().all(|c| letters.contains(&c))), "{:?}", text);
+    }
+
+    #[test]
+    fn dead_ends_start_over_or_give_up() {
+        // After "aa" only the "x" of "aax" may follow, so that path is a dead end.
+        let markov = MarkovGenerator::characters(&words("aax ab"));
+        let text = markov.generate(&['a', 'b'], 20, &mut ChaCha8Rng::seed_from_u64(1)).unwrap();
+        assert!(text.iter().all(|w| w == "ab"), "{:?}", text);
+
+        assert_eq!(
+            markov.generate(&['x'], 5, &mut ChaCha8Rng::seed_from_u64(1)),
+            Err(GeneratorError::NotEnoughWords { available: 0, required: 5 })
+        );
+    }
+
+    #[test]
+    fn phrases_follow_the_corpus() {
+        let markov = MarkovGenerator::words("The cat sat. The dog ran!");
+        let text = markov.generate(&[], 12, &mut ChaCha8Rng::seed_from_u64(1)).unwrap();
+        assert_eq!(text.len(), 12);
+        for pair in text.windows(2) {
+            let follows = ["the cat", "the dog", "cat sat", "dog ran", "sat the", "ran the"];
+            assert!(follows.contains(&pair.join(" ").as_str()), "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn the_bundled_corpus_gives_varied_phrases() {
+        let markov = MarkovGenerator::words(include_str!("../res/markov/corpus.txt"));
+        let mut text = markov.generate(&[], 200, &mut ChaCha8Rng::seed_from_u64(1)).unwrap();
+        text.sort();
+        text.dedup();
+        assert!(text.len() > 100, "only {} different words", text.len());
+    }
+}
@@ -0,0 +1,31 @@
+use std::path::PathBuf;
+
+/// Command line shared by the front ends: `[--seed N] [FILE]`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Options {
+    /// Text or Markdown file practiced instead of generated text, see `DocumentSource`.
+    pub document: Option<PathBuf>,
+    /// Seed every test is generated from, see `TypingSession::set_seed`.
+    pub seed: Option<u64>,
+}
+
+impl Options {
+    /// Parses the arguments following the program name.
+    pub fn parse(args: impl Iterator<Item = String>) -> Result<Options, String> {
+        let mut options = Options::default();
+        let mut args = args;
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--seed" => {
+                    let seed = args.next().ok_or("--seed needs a number")?;
+                    options.seed = Some(seed.parse().map_err(|_| format!("invalid seed {}", seed))?);
+                }
+                _ if options.document.is_none() && !arg.starts_with("--") => {
+                    options.document = Some(PathBuf::from(arg))
+                }
+                _ => return Err(format!("unexpected argument {}, usage: [--seed N] [FILE]", arg)),
+            }
+        }
+        Ok(options)
+    }
+}
@@ -0,0 +1,219 @@
+use std::fs;
+
+use rand_chacha::ChaCha8Rng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::document;
+use crate::storage;
+use crate::typing_session::TextSource;
+use crate::typing_statistic::TypingStatistic;
+
+/// Corpus of passages, looked up in the config directory and in `res`.
+const PASSAGES_FILE: &str = "passages.json";
+
+/// The bundled corpus built into the program, for when `res` can't be found.
+const BUNDLED_PASSAGES: &str = include_str!("../res/passages.json");
+
+/// Passages up to this many characters are short, up to `LONG_PASSAGE` medium, longer ones long.
+const SHORT_PASSAGE: usize = 100;
+const LONG_PASSAGE: usize = 300;
+
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub enum PassageLength {
+    Short,
+    Medium,
+    Long,
+}
+
+impl PassageLength {
+    pub fn of(text: &str) -> PassageLength {
+        match text.chars().count() {
+            n if n <= SHORT_PASSAGE => PassageLength::Short,
+            n if n <= LONG_PASSAGE => PassageLength::Medium,
+            _ => PassageLength::Long
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            PassageLength::Short => "short",
+            PassageLength::Medium => "medium",
+            PassageLength::Long => "long"
+        }
+    }
+}
+
+/// A quote or an excerpt of a book.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Passage {
+    /// Identifies the passage in the statistic, the text itself when missing.
+    #[serde(default)]
+    pub id: String,
+    pub text: String,
+    /// Author and work the passage is taken from.
+    pub source: String,
+}
+
+impl Passage {
+    pub fn length(&self) -> PassageLength {
+        PassageLength::of(&self.text)
+    }
+}
+
+/// Draws random passages of one length from the bundled corpus in `res/passages.json` and the
+/// user's own `passages.json` in the config directory.
+pub struct PassageSource {
+    passages: Vec<Passage>,
+    length: PassageLength,
+    current: Option<usize>,
+    /// Passage last typed to its end, its source is shown with the results.
+    completed: Option<usize>,
+    /// Passage files that couldn't be read, shown in the label.
+    warning: Option<String>,
+    rng: ChaCha8Rng,
+}
+
+impl PassageSource {
+    pub fn load(length: PassageLength) -> PassageSource {
+        let (mut passages, mut failures) = (vec![], vec![]);
+        let (own, bundled) = (storage::config_dir().join(PASSAGES_FILE), storage::resource_dir().join(PASSAGES_FILE));
+        let files = [
+            (&own, fs::read_to_string(&own).ok()),
+            (&bundled, Some(fs::read_to_string(&bundled).unwrap_or_else(|_| BUNDLED_PASSAGES.to_string()))),
+        ];
+        for (path, contents) in files.iter() {
+            let contents = match contents {
+                Some(contents) => contents,
+                None => continue,
+            };
+            match serde_json::from_str::<Vec<Passage>>(contents) {
+                Ok(file) => passages.extend(file),
+                Err(e) => failures.push(format!("failed to read passages from {}: {}", path.display(), e)),
+            }
+        }
+        let mut source = PassageSource::new(passages, length);
+        if !failures.is_empty() {
+            source.warning = Some(failures.join(", "));
+        }
+        source
+    }
+
+    /// Passages are normalized like documents, so typographic punctuation can be typed.
+    pub fn new(passages: Vec<Passage>, length: PassageLength) -> PassageSource {
+        let passages = passages.into_iter()
+            .map(|p| {
+                let text = document::normalize(&p.text);
+                let id = if p.id.is_empty() { text.clone() } else { p.id };
+                Passage { id, text, source: p.source }
+            })
+            .filter(|p| !p.text.is_empty())
+            .collect();
+        PassageSource { passages, length, current: None, completed: None, warning: None, rng: ChaCha8Rng::from_entropy() }
+    }
+
+    pub fn passages(&self) -> &[Passage] {
+        &self.passages
+    }
+
+    /// Passage being typed.
+    pub fn current(&self) -> Option<&Passage> {
+        self.current.map(|i| &self.passages[i])
+    }
+}
+
+impl TextSource for PassageSource {
+    /// A random passage of the chosen length, any passage if there is none of that length.
+    fn next_text(&mut self, _statistic: &TypingStatistic) -> String {
+        let mut candidates: Vec<usize> = (0..self.passages.len())
+            .filter(|i| self.passages[*i].length() == self.length)
+            .collect();
+        if candidates.is_empty() {
+            candidates = (0..self.passages.len()).collect();
+        }
+        if candidates.is_empty() {
+            return String::new();
+        }
+        if candidates.len() > 1 {
+            candidates.retain(|i| Some(*i) != self.current);
+        }
+        let next = candidates[self.rng.gen_range(0, candidates.len())];
+        self.current = Some(next);
+        self.passages[next].text.clone()
+    }
+
+    fn text_completed(&mut self) {
+        self.completed = self.current;
+    }
+
+    fn reseed(&mut self, seed: u64) {
+        self.rng = ChaCha8Rng::seed_from_u64(seed);
+        self.current = None;
+    }
+
+    fn label(&self) -> String {
+        let label = match self.completed {
+            _ if self.passages.is_empty() => "Passages: none found".to_string(),
+            Some(completed) => format!(
+                "Passages: {}, last one from {}",
+                self.length.description(), self.passages[completed].source
+            ),
+            None => format!("Passages: {}", self.length.description()),
+        };
+        match &self.warning {
+            Some(warning) => format!("{}, {}", label, warning),
+            None => label,
+        }
+    }
+
+    fn attribution(&self) -> Option<String> {
+        self.completed.map(|i| self.passages[i].source.clone())
+    }
+
+    fn text_id(&self) -> Option<String> {
+        self.current().map(|p| p.id.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn passage(id: &str, text: &str) -> Passage {
+        Passage { id: id.to_string(), text: text.to_string(), source: format!("{} source", text) }
+    }
+
+    #[test]
+    fn passages_of_the_chosen_length_come_first() {
+        let statistic = TypingStatistic::new();
+        let long = "long ".repeat(LONG_PASSAGE / 4);
+        let passages = vec![passage("", "short"), passage("a", "also short"), passage("b", &long)];
+
+        let mut source = PassageSource::new(passages.clone(), PassageLength::Short);
+        source.reseed(1);
+        let first = source.next_text(&statistic);
+        let second = source.next_text(&statistic);
+        assert_ne!(first, second);
+        assert!(PassageLength::of(&first) == PassageLength::Short && PassageLength::of(&second) == PassageLength::Short);
+        // The text identifies a passage without an id.
+        let id = if second == "short" { "short" } else { "a" };
+        assert_eq!(source.text_id(), Some(id.to_string()));
+        assert_eq!(source.attribution(), None);
+        source.text_completed();
+        assert_eq!(source.attribution(), Some(format!("{} source", second)));
+
+        // There are no medium passages, any one will do.
+        let mut source = PassageSource::new(passages, PassageLength::Medium);
+        assert!(!source.next_text(&statistic).is_empty());
+        assert_eq!(source.label(), "Passages: medium");
+    }
+
+    #[test]
+    fn no_passages_give_no_text() {
+        let mut source = PassageSource::new(vec![passage("", "  ")], PassageLength::Short);
+        assert!(source.passages().is_empty());
+        assert_eq!(source.next_text(&TypingStatistic::new()), "");
+        assert_eq!(source.text_id(), None);
+        assert_eq!(source.label(), "Passages: none found");
+    }
+}
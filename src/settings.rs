@@ -0,0 +1,216 @@
+use std::cell::RefCell;
+use std::io;
+use std::rc::Rc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::augmentation::Augmentation;
+use crate::code_source::CodeSource;
+use crate::lesson::Lesson;
+use crate::passage_source::{PassageLength, PassageSource};
+use crate::storage;
+use crate::text_generator::TextGenerator;
+use crate::typing_session::{ErrorPolicy, TestMode, TextSource};
+use crate::typing_statistic::PersonalBest;
+use crate::word_list::{self, DEFAULT_LIST};
+use crate::word_source::{GenerationMode, WordSource};
+
+const SETTINGS_FILE: &str = "settings.json";
+
+/// What the practice texts are made of, unless a document is given on the command line.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub enum Content {
+    #[default]
+    Words,
+    Code,
+    /// Quotes and book excerpts of the given length.
+    Passages(PassageLength),
+}
+
+impl Content {
+    pub fn description(&self) -> String {
+        match self {
+            Content::Words => "words".to_string(),
+            Content::Code => "code".to_string(),
+            Content::Passages(length) => format!("{} passages", length.description())
+        }
+    }
+
+    pub fn next(&self) -> Content {
+        match self {
+            Content::Words => Content::Code,
+            Content::Code => Content::Passages(PassageLength::Short),
+            Content::Passages(PassageLength::Short) => Content::Passages(PassageLength::Medium),
+            Content::Passages(PassageLength::Medium) => Content::Passages(PassageLength::Long),
+            Content::Passages(PassageLength::Long) => Content::Words
+        }
+    }
+
+    /// Source of texts of this kind, `words` stays shared so the front end can keep configuring it.
+    pub fn source(&self, words: &Rc<RefCell<WordSource>>) -> Box<dyn TextSource> {
+        match self {
+            Content::Words => Box::new(words.clone()),
+            Content::Code => Box::new(CodeSource::load()),
+            Content::Passages(length) => Box::new(PassageSource::load(*length)),
+        }
+    }
+
+    /// What spaces are drawn as. Code has underscores of its own and indentation, so its spaces
+    /// are drawn as middle dots.
+    pub fn space_symbol(&self) -> &'static str {
+        match self {
+            Content::Code => "\u{B7}",
+            _ => "_"
+        }
+    }
+}
+
+/// How the window marks the letter to type next.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub enum CaretStyle {
+    /// A box behind the letter.
+    Block,
+    /// A bar before the letter.
+    #[default]
+    Line,
+    /// A bar under the letter.
+    Underline,
+}
+
+impl CaretStyle {
+    pub fn description(&self) -> &'static str {
+        match self {
+            CaretStyle::Block => "block",
+            CaretStyle::Line => "line",
+            CaretStyle::Underline => "underline"
+        }
+    }
+
+    pub fn next(&self) -> CaretStyle {
+        match self {
+            CaretStyle::Block => CaretStyle::Line,
+            CaretStyle::Line => CaretStyle::Underline,
+            CaretStyle::Underline => CaretStyle::Block
+        }
+    }
+}
+
+/// Where the window shows what was typed instead of a wrong letter.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub enum TypedDisplay {
+    Hidden,
+    /// In small print above the expected letter.
+    Above,
+    /// In small print below the expected letter.
+    #[default]
+    Below,
+    /// Instead of the expected letter.
+    InPlace,
+}
+
+impl TypedDisplay {
+    pub fn description(&self) -> &'static str {
+        match self {
+            TypedDisplay::Hidden => "hidden",
+            TypedDisplay::Above => "above",
+            TypedDisplay::Below => "below",
+            TypedDisplay::InPlace => "in place"
+        }
+    }
+
+    pub fn next(&self) -> TypedDisplay {
+        match self {
+            TypedDisplay::Hidden => TypedDisplay::Above,
+            TypedDisplay::Above => TypedDisplay::Below,
+            TypedDisplay::Below => TypedDisplay::InPlace,
+            TypedDisplay::InPlace => TypedDisplay::Hidden
+        }
+    }
+}
+
+/// User preferences shared by all front ends.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub error_policy: ErrorPolicy,
+    pub test_mode: TestMode,
+    pub generation: GenerationMode,
+    /// Restricts the words to the letters unlocked in the keyboard lesson, see `Lesson`.
+    pub lesson: bool,
+    /// How strongly adaptive generation favors weak keys, see `WordSource::set_mode`.
+    pub adaptive_strength: f64,
+    /// Name of the word list, see `WordList`.
+    pub word_list: String,
+    /// Only the most common words of the list are used when set.
+    pub word_limit: Option<usize>,
+    /// Flattens frequency-weighted sampling above 1, sharpens it below, see `TextGenerator`.
+    pub frequency_temperature: f64,
+    pub augmentation: Augmentation,
+    pub content: Content,
+    pub caret_style: CaretStyle,
+    /// Highlights the whole word under the caret.
+    pub highlight_word: bool,
+    /// Lets the caret glide to the next letter instead of jumping there.
+    pub smooth_caret: bool,
+    pub typed_display: TypedDisplay,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            error_policy: ErrorPolicy::default(),
+            test_mode: TestMode::default(),
+            generation: GenerationMode::default(),
+            lesson: false,
+            adaptive_strength: 1.0,
+            word_list: DEFAULT_LIST.to_string(),
+            word_limit: None,
+            frequency_temperature: 1.0,
+            augmentation: Augmentation::default(),
+            content: Content::default(),
+            caret_style: CaretStyle::default(),
+            highlight_word: false,
+            smooth_caret: true,
+            typed_display: TypedDisplay::default(),
+        }
+    }
+}
+
+impl Settings {
+    pub fn load() -> Settings {
+        storage::load(SETTINGS_FILE).unwrap_or_default()
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        storage::save(SETTINGS_FILE, self)
+    }
+
+    /// The keyboard lesson when it is turned on.
+    pub fn lesson(&self) -> Option<Lesson> {
+        if self.lesson { Some(Lesson::load()) } else { None }
+    }
+
+    /// The chosen word list at the chosen temperature. The default list is used when it can't be
+    /// loaded, `warning` then tells why.
+    pub fn load_words(&self, warning: &mut Option<String>) -> TextGenerator {
+        let mut generator = word_list::open(&self.word_list, self.word_limit).unwrap_or_else(|e| {
+            *warning = Some(format!("failed to load word list: {}, using the default list", e));
+            word_list::default_list(self.word_limit)
+        });
+        generator.set_temperature(self.frequency_temperature);
+        generator
+    }
+}
+
+pub fn personal_best_text(best: &PersonalBest) -> String {
+    if best.is_new {
+        format!("New personal best: {:.1} wpm after {} attempts", best.net_wpm, best.attempts)
+    } else {
+        format!("Personal best: {:.1} wpm, {} attempts", best.net_wpm, best.attempts)
+    }
+}
+
+/// Spaces of keys and substitutions are shown as underscores, like in the text.
+pub fn visible(letter: &str) -> String {
+    if letter == " " { "_".to_string() } else { letter.to_string() }
+}
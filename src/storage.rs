@@ -0,0 +1,57 @@
+use std::env;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// Directory all persistent files live in, e.g. `~/.local/share/rtyping` on Linux.
+pub fn data_dir() -> PathBuf {
+    dirs::data_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("rtyping")
+}
+
+/// Directory users put their own word lists and snippets in, e.g. `~/.config/rtyping` on Linux.
+pub fn config_dir() -> PathBuf {
+    dirs::config_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("rtyping")
+}
+
+/// Resources shipped with the program: `res` next to the executable, `../share/rtyping` for
+/// installed binaries, or `res` in the source tree it was built from.
+pub fn resource_dir() -> PathBuf {
+    let source_tree = PathBuf::from(concat!(env!("CARGO_MANIFEST_DIR"), "/res"));
+    let installed = env::current_exe().ok()
+        .and_then(|exe| exe.parent().map(|dir| vec![dir.join("res"), dir.join("../share/rtyping")]))
+        .unwrap_or_default();
+    installed.into_iter()
+        .find(|dir| dir.is_dir())
+        .unwrap_or(source_tree)
+}
+
+/// Reads `name` from the data directory. Missing or unreadable files yield `None`.
+pub fn load<T: DeserializeOwned>(name: &str) -> Option<T> {
+    serde_json::from_str(&read(name).ok()??).ok()
+}
+
+/// Contents of `name` in the data directory, `None` if it doesn't exist yet.
+pub fn read(name: &str) -> io::Result<Option<String>> {
+    match fs::read_to_string(data_dir().join(name)) {
+        Ok(contents) => Ok(Some(contents)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Writes `name` to the data directory. The file is replaced at once, so an interrupted save
+/// leaves the previous contents intact.
+pub fn save<T: Serialize>(name: &str, value: &T) -> io::Result<()> {
+    let dir = data_dir();
+    fs::create_dir_all(&dir)?;
+    let temporary = dir.join(format!("{}.tmp", name));
+    fs::write(&temporary, serde_json::to_string_pretty(value)?)?;
+    fs::rename(&temporary, dir.join(name))
+}
@@ -1,26 +1,341 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+
+use rand::distributions::{Distribution, WeightedIndex};
 use rand::Rng;
+use unicode_normalization::UnicodeNormalization;
+
+/// Smallest amount of distinct words a letter set has to match before text is generated from it.
+pub const MIN_WORDS: usize = 10;
+
+/// Only the first `MAX_LETTERS` distinct characters of a word list can be used in a letter set.
+const MAX_LETTERS: usize = 128;
+
+/// Random candidates weighed against each other by `generate_adaptive`.
+const ADAPTIVE_POOL: usize = 1000;
+
+/// Lowest temperature accepted by `set_temperature`, close to always picking the most common word.
+const MIN_TEMPERATURE: f64 = 0.01;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GeneratorError {
+    NotEnoughWords { available: usize, required: usize },
+}
+
+impl fmt::Display for GeneratorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GeneratorError::NotEnoughWords { available, required } => write!(
+                f,
+                "only {} words can be built from the given letters, at least {} are required",
+                available, required
+            ),
+        }
+    }
+}
+
+impl std::error::Error for GeneratorError {}
+
+/// Keys and sequences adaptive generation puts into the text more often.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WeakSpots {
+    pub keys: Vec<String>,
+    pub sequences: Vec<String>,
+}
+
+impl WeakSpots {
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty() && self.sequences.is_empty()
+    }
+
+    /// Number of weak keys and sequences found in `word`.
+    fn hits(&self, word: &str) -> usize {
+        self.keys.iter().filter(|k| word.contains(k.as_str())).count()
+            + self.sequences.iter().filter(|s| word.contains(s.as_str())).count()
+    }
+}
+
+/// Walker's alias method: draws an index proportionally to its weight in constant time.
+struct AliasTable{
+    probability: Vec<f64>,
+    alias: Vec<usize>,
+}
+
+impl AliasTable{
+    /// `None` if the weights don't add up to a positive finite total.
+    fn new(weights: &[f64]) -> Option<AliasTable>{
+        let n = weights.len();
+        let total: f64 = weights.iter().sum();
+        if n == 0 || !total.is_finite() || total <= 0.0 {
+            return None;
+        }
+
+        let mut scaled: Vec<f64> = weights.iter().map(|w| w * n as f64 / total).collect();
+        let (mut small, mut large): (Vec<usize>, Vec<usize>) = (0..n).partition(|i| scaled[*i] < 1.0);
+        let mut probability = vec![1.0; n];
+        let mut alias: Vec<usize> = (0..n).collect();
+        while let (Some(s), Some(&l)) = (small.last().cloned(), large.last()) {
+            small.pop();
+            probability[s] = scaled[s];
+            alias[s] = l;
+            scaled[l] -= 1.0 - scaled[s];
+            if scaled[l] < 1.0 {
+                large.pop();
+                small.push(l);
+            }
+        }
+        // Whatever is left over is 1 up to rounding errors and keeps its own slot.
+        Some(AliasTable{ probability, alias })
+    }
+
+    fn sample<R: Rng>(&self, rng: &mut R) -> usize{
+        let i = rng.gen_range(0, self.probability.len());
+        if rng.gen::<f64>() < self.probability[i] { i } else { self.alias[i] }
+    }
+}
+
+/// Words made only of a given letter set.
+struct Candidates{
+    mask: u128,
+    words: Vec<usize>,
+    /// Frequency weights of `words`, missing when every word is equally likely.
+    frequencies: Option<AliasTable>,
+}
+
+impl Candidates{
+    fn sample<R: Rng>(&self, rng: &mut R) -> usize{
+        match &self.frequencies {
+            Some(table) => self.words[table.sample(rng)],
+            None => self.words[rng.gen_range(0, self.words.len())],
+        }
+    }
+}
 
 pub struct TextGenerator{
-    words: Vec<String>
+    words: Vec<String>,
+    /// Occurrences of every word in some corpus, empty for lists without a frequency column.
+    counts: Vec<f64>,
+    /// Counts are raised to the power of `1 / temperature`: 1 samples proportionally, higher
+    /// values flatten the distribution towards uniform, lower ones sharpen it.
+    temperature: f64,
+    /// Bit position of every character used in `words`.
+    alphabet: HashMap<char, u32>,
+    /// Words grouped by the exact set of letters they are made of.
+    groups: Vec<(u128, Vec<usize>)>,
+    /// Words matching the letter set of the last `generate` call.
+    candidates: RefCell<Option<Candidates>>,
 }
 
 impl TextGenerator{
 
+    /// Takes a word list with one word per line, see `word_list` for reading one from disk.
+    /// Lines may be `word<TAB>count`, words are then picked proportionally to their count.
+    /// Words are normalized to NFC like the typed input, see `typing_session::letters`.
     pub fn new(contents: &str) -> TextGenerator{
-        //let mut file = File::open(path).unwrap();
-        //let mut contents = String::new();
-        //file.read_to_string(&mut contents).unwrap();
+        let mut words: Vec<String> = vec![];
+        let mut counts: Vec<f64> = vec![];
+        let mut weighted = false;
+        for line in contents.lines() {
+            let mut columns = line.split('\t');
+            let word = columns.next().unwrap_or("").trim();
+            if word.is_empty() {
+                continue;
+            }
+            let count = columns.next().and_then(|c| c.trim().parse::<f64>().ok());
+            weighted |= count.is_some();
+            words.push(word.nfc().collect());
+            counts.push(count.filter(|c| *c > 0.0).unwrap_or(1.0));
+        }
+        if !weighted {
+            counts.clear();
+        }
+
+        let mut alphabet = HashMap::new();
+        let mut groups: HashMap<u128, Vec<usize>> = HashMap::new();
+        for (i, word) in words.iter().enumerate() {
+            let mut mask = 0u128;
+            let mut indexed = true;
+            for c in word.chars() {
+                let next = alphabet.len() as u32;
+                if !alphabet.contains_key(&c) && alphabet.len() >= MAX_LETTERS {
+                    indexed = false;
+                    break;
+                }
+                mask |= 1 << *alphabet.entry(c).or_insert(next);
+            }
+            if indexed {
+                groups.entry(mask).or_insert(vec![]).push(i);
+            }
+        }
+
+        let mut groups: Vec<(u128, Vec<usize>)> = groups.into_iter().collect();
+        groups.sort_by_key(|(mask, _)| *mask);
 
-        TextGenerator{ words: contents.split('\n').map(|w|w.to_string()).collect() }
+        TextGenerator{
+            words,
+            counts,
+            temperature: 1.0,
+            alphabet,
+            groups,
+            candidates: RefCell::new(None),
+        }
+    }
+
+    pub fn words(&self) -> &[String]{
+        &self.words
+    }
+
+    /// Whether the word list came with frequency counts.
+    pub fn is_weighted(&self) -> bool{
+        !self.counts.is_empty()
+    }
+
+    /// See `temperature`, has no effect on lists without frequency counts.
+    pub fn set_temperature(&mut self, temperature: f64){
+        self.temperature = temperature.max(MIN_TEMPERATURE);
+        *self.candidates.borrow_mut() = None;
+    }
+
+    /// Number of words made only of `chars`. An empty letter set allows every word.
+    pub fn available_words(&self, chars: &[char]) -> usize{
+        self.with_candidates(chars, |candidates| candidates.words.len())
+    }
+
+    /// Picks `len` random words made only of `chars`. An empty letter set allows every word.
+    /// A seeded `rng` always yields the same words for the same list.
+    pub fn generate<R: Rng>(&self, chars: &[char], len: usize, rng: &mut R) -> Result<Vec<String>, GeneratorError>{
+        self.generate_adaptive(chars, len, &WeakSpots::default(), 0.0, rng)
+    }
+
+    /// Like `generate`, but favors words containing `weak` keys and sequences: each one found in
+    /// a word multiplies its weight by `1 + strength`.
+    pub fn generate_adaptive<R: Rng>(&self, chars: &[char], len: usize, weak: &WeakSpots, strength: f64, rng: &mut R)
+        -> Result<Vec<String>, GeneratorError>{
+        self.with_candidates(chars, |candidates| {
+            check_available(candidates.words.len())?;
+            if weak.is_empty() || strength <= 0.0 {
+                return Ok((0..len)
+                    .map(|_| self.words[candidates.sample(rng)].clone())
+                    .collect());
+            }
+
+            let pool: Vec<&String> = (0..ADAPTIVE_POOL)
+                .map(|_| &self.words[candidates.sample(rng)])
+                .collect();
+            let weights = pool.iter().map(|word| (1.0 + strength).powi(weak.hits(word) as i32));
+            Ok(match WeightedIndex::new(weights) {
+                Ok(index) => (0..len).map(|_| pool[index.sample(rng)].clone()).collect(),
+                // Weights overflowed, the pool is random enough on its own.
+                Err(_) => pool.into_iter().take(len).cloned().collect()
+            })
+        })
+    }
+
+    /// An empty letter set allows every letter.
+    fn letter_mask(&self, chars: &[char]) -> u128{
+        if chars.is_empty() {
+            return !0;
+        }
+        chars.iter()
+            .filter_map(|c| self.alphabet.get(c))
+            .fold(0, |mask, bit| mask | 1 << *bit)
+    }
+
+    fn with_candidates<T>(&self, chars: &[char], f: impl FnOnce(&Candidates) -> T) -> T{
+        let allowed = self.letter_mask(chars);
+        let mut cache = self.candidates.borrow_mut();
+        match &*cache {
+            Some(candidates) if candidates.mask == allowed => {}
+            _ => {
+                let words: Vec<usize> = self.groups.iter()
+                    .filter(|(mask, _)| mask & !allowed == 0)
+                    .flat_map(|(_, words)| words.iter().cloned())
+                    .collect();
+                let frequencies = self.frequency_table(&words);
+                *cache = Some(Candidates{ mask: allowed, words, frequencies });
+            }
+        }
+        f(cache.as_ref().unwrap())
     }
 
-    pub fn generate(&self, chars: &Vec<char>, len: usize) -> Vec<String>{
-        let mut res: Vec<String> = vec![];
-        for _i in 0..len{
-            let mut rng = rand::thread_rng();
-            let word = rng.gen_range(0, self.words.len());
-            res.push(self.words[word].clone());
+    fn frequency_table(&self, words: &[usize]) -> Option<AliasTable>{
+        if !self.is_weighted() {
+            return None;
         }
-        res
+        // Relative to the most common word in log space, so low temperatures can't overflow.
+        let logs: Vec<f64> = words.iter().map(|i| self.counts[*i].ln()).collect();
+        let max = logs.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+        let weights: Vec<f64> = logs.iter().map(|l| ((l - max) / self.temperature).exp()).collect();
+        AliasTable::new(&weights)
     }
-}
\ No newline at end of file
+}
+
+fn check_available(available: usize) -> Result<(), GeneratorError>{
+    if available < MIN_WORDS {
+        return Err(GeneratorError::NotEnoughWords { available, required: MIN_WORDS });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    /// Twelve words of the letters `a`, `b` and `c`, and some using other letters.
+    const WORDS: &str = "a\nb\nc\nab\nba\nac\nca\nbc\ncb\nabc\ncab\nbca\ndog\nfish\nzebra";
+
+    #[test]
+    fn only_words_of_the_given_letters_are_drawn() {
+        let generator = TextGenerator::new(WORDS);
+        let letters = vec!['a', 'b', 'c'];
+        assert_eq!(generator.available_words(&letters), 12);
+        let words = generator.generate(&letters, 200, &mut ChaCha8Rng::seed_from_u64(1)).unwrap();
+        assert_eq!(words.len(), 200);
+        assert!(words.iter().all(|w| w.chars().all(|c| letters.contains(&c))));
+    }
+
+    #[test]
+    fn too_few_words_are_an_error() {
+        let generator = TextGenerator::new(WORDS);
+        assert_eq!(
+            generator.generate(&['a', 'b'], 5, &mut ChaCha8Rng::seed_from_u64(1)),
+            Err(GeneratorError::NotEnoughWords { available: 4, required: MIN_WORDS })
+        );
+        assert_eq!(generator.available_words(&['x']), 0);
+    }
+
+    #[test]
+    fn an_empty_letter_set_allows_every_word() {
+        let generator = TextGenerator::new(WORDS);
+        assert_eq!(generator.available_words(&[]), 15);
+        let words = generator.generate(&[], 500, &mut ChaCha8Rng::seed_from_u64(1)).unwrap();
+        assert!(words.iter().any(|w| w == "zebra"));
+    }
+
+    #[test]
+    fn seeded_draws_follow_the_weights() {
+        let table = AliasTable::new(&[1.0, 2.0, 7.0]).unwrap();
+        let mut rng = ChaCha8Rng::seed_from_u64(1);
+        let mut drawn = [0usize; 3];
+        for _ in 0..10_000 {
+            drawn[table.sample(&mut rng)] += 1;
+        }
+        for (count, expected) in drawn.iter().zip(&[1_000, 2_000, 7_000]) {
+            assert!((*count as i64 - expected).abs() < 200, "{:?}", drawn);
+        }
+        assert!(AliasTable::new(&[0.0, 0.0]).is_none());
+        assert!(AliasTable::new(&[]).is_none());
+    }
+
+    #[test]
+    fn low_temperatures_pick_the_most_common_word() {
+        let words: String = (0..MIN_WORDS).map(|i| format!("w{}\t{}\n", i, 1e300 / (i + 1) as f64)).collect();
+        let mut generator = TextGenerator::new(&words);
+        generator.set_temperature(0.0);
+        let words = generator.generate(&[], 100, &mut ChaCha8Rng::seed_from_u64(1)).unwrap();
+        assert!(words.iter().all(|w| w == "w0"));
+    }
+}
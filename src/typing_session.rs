@@ -0,0 +1,870 @@
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use unicode_normalization::UnicodeNormalization;
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::typing_statistic::{TypingState, TypingStatistic};
+
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub enum Pressed {
+    Pressed,
+    #[default]
+    NotPressed,
+    WrongPressed
+}
+
+impl Pressed{
+    /// Hex color of a letter in this state, shared by all front ends.
+    pub fn color(&self) -> &'static str {
+        match self {
+            Pressed::Pressed => "#239B56",
+            Pressed::NotPressed => "#E5E7E9",
+            Pressed::WrongPressed => "#E74C3C"
+        }
+    }
+}
+
+#[derive(Default, Clone)]
+pub struct KeyLetter{
+    /// One grapheme cluster, like `é` or `ё`, which may take several chars or a composed keystroke.
+    pub character: String,
+    pub pressed: Pressed,
+    /// What was typed instead of `character` while it is `WrongPressed`.
+    pub typed: Option<String>,
+    /// Whether a correct and a wrong press of the letter were counted. Each is counted once,
+    /// however often the letter is corrected and typed again.
+    counted_correct: bool,
+    counted_wrong: bool
+}
+
+impl KeyLetter{
+    pub fn new(character: String, pressed: Pressed) -> KeyLetter{
+        KeyLetter{character, pressed, typed: None, counted_correct: false, counted_wrong: false}
+    }
+
+    /// Marks a correct or wrong press as counted, returns whether it is the first of its kind.
+    fn count(&mut self, correct: bool) -> bool{
+        let counted = if correct { &mut self.counted_correct } else { &mut self.counted_wrong };
+        !std::mem::replace(counted, true)
+    }
+
+    /// Marks the letter as not typed yet.
+    fn clear(&mut self){
+        self.pressed = Pressed::NotPressed;
+        self.typed = None;
+    }
+
+    pub fn is_whitespace(&self) -> bool{
+        self.character.chars().all(char::is_whitespace)
+    }
+}
+
+/// Splits `text` into the letters compared against typed input. Both are normalized to NFC, so
+/// a precomposed `é` matches `e` followed by a combining accent.
+pub fn letters(text: &str) -> Vec<String>{
+    text.nfc().collect::<String>().graphemes(true).map(|g| g.to_string()).collect()
+}
+
+/// Source of the current time, so sessions can be driven without waiting for real time to pass.
+pub trait Clock {
+    fn now(&self) -> Instant;
+}
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// Clock that only moves when advanced, used by tests and when replaying recorded input.
+pub struct ManualClock {
+    now: Cell<Instant>,
+}
+
+impl Default for ManualClock {
+    fn default() -> Self {
+        ManualClock::new()
+    }
+}
+
+impl ManualClock {
+    pub fn new() -> Self {
+        ManualClock { now: Cell::new(Instant::now()) }
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        self.now.set(self.now.get() + duration);
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        self.now.get()
+    }
+}
+
+impl<C: Clock> Clock for Rc<C> {
+    fn now(&self) -> Instant {
+        (**self).now()
+    }
+}
+
+/// Provides the text of every new sample.
+pub trait TextSource {
+    fn next_text(&mut self, statistic: &TypingStatistic) -> String;
+
+    /// Short description of what is being practiced, shown next to the text.
+    fn label(&self) -> String {
+        String::new()
+    }
+
+    /// Asks for texts of `words` words, sources of fixed texts ignore it.
+    fn set_word_count(&mut self, _words: usize) {}
+
+    /// Called once the last text was typed to its end, as opposed to being skipped.
+    fn text_completed(&mut self) {}
+
+    /// Makes the following texts depend on `seed` only, sources without randomness ignore it.
+    fn reseed(&mut self, _seed: u64) {}
+
+    /// Author or work the text last typed to its end is taken from, shown with the results.
+    fn attribution(&self) -> Option<String> {
+        None
+    }
+
+    /// Identifies the current text if the source serves it again later, so results on it can be
+    /// compared, see `TypingStatistic::last_sample_best`.
+    fn text_id(&self) -> Option<String> {
+        None
+    }
+}
+
+impl<F: FnMut(&TypingStatistic) -> String> TextSource for F {
+    fn next_text(&mut self, statistic: &TypingStatistic) -> String {
+        self(statistic)
+    }
+}
+
+/// Lets a front end keep configuring a source after handing it to a session.
+impl<T: TextSource> TextSource for Rc<RefCell<T>> {
+    fn next_text(&mut self, statistic: &TypingStatistic) -> String {
+        self.borrow_mut().next_text(statistic)
+    }
+
+    fn label(&self) -> String {
+        self.borrow().label()
+    }
+
+    fn set_word_count(&mut self, words: usize) {
+        self.borrow_mut().set_word_count(words)
+    }
+
+    fn text_completed(&mut self) {
+        self.borrow_mut().text_completed()
+    }
+
+    fn reseed(&mut self, seed: u64) {
+        self.borrow_mut().reseed(seed)
+    }
+
+    fn attribution(&self) -> Option<String> {
+        self.borrow().attribution()
+    }
+
+    fn text_id(&self) -> Option<String> {
+        self.borrow().text_id()
+    }
+}
+
+/// What happens to the cursor when a wrong key is pressed.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub enum ErrorPolicy {
+    /// The cursor stays on the letter until the right key is pressed.
+    #[default]
+    MustCorrect,
+    /// The letter is marked wrong and the cursor moves on.
+    Skip,
+}
+
+impl ErrorPolicy {
+    pub fn description(&self) -> &'static str {
+        match self {
+            ErrorPolicy::MustCorrect => "errors must be corrected",
+            ErrorPolicy::Skip => "errors are skipped"
+        }
+    }
+
+    pub fn toggled(&self) -> ErrorPolicy {
+        match self {
+            ErrorPolicy::MustCorrect => ErrorPolicy::Skip,
+            ErrorPolicy::Skip => ErrorPolicy::MustCorrect
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub enum TestMode {
+    /// A sample is recorded for every completed text, then a new one is generated.
+    #[default]
+    Practice,
+    /// Text keeps coming until the given number of seconds runs out.
+    Timed(u64),
+    /// The test ends as soon as the given number of words is typed.
+    Words(usize),
+}
+
+/// Order in which front ends cycle through the test modes.
+const TEST_MODES: [TestMode; 9] = [
+    TestMode::Practice,
+    TestMode::Timed(15),
+    TestMode::Timed(30),
+    TestMode::Timed(60),
+    TestMode::Timed(120),
+    TestMode::Words(10),
+    TestMode::Words(25),
+    TestMode::Words(50),
+    TestMode::Words(100),
+];
+
+/// Words per text outside of the word count mode.
+const DEFAULT_WORDS: usize = 20;
+
+/// Random seeds stay below this so they are easy to share.
+const MAX_SEED: u64 = 1_000_000;
+
+/// Times a source is asked for a text before the session gives up on it.
+const TEXT_ATTEMPTS: usize = 3;
+
+impl TestMode {
+    pub fn description(&self) -> String {
+        match self {
+            TestMode::Practice => "practice".to_string(),
+            TestMode::Timed(seconds) => format!("{} s", seconds),
+            TestMode::Words(words) => format!("{} words", words)
+        }
+    }
+
+    pub fn next(&self) -> TestMode {
+        let index = TEST_MODES.iter().position(|m| m == self).map_or(0, |i| i + 1);
+        TEST_MODES[index % TEST_MODES.len()]
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Outcome {
+    /// The key matched the letter under the cursor.
+    Correct,
+    /// The key did not match the letter under the cursor.
+    Wrong,
+    /// Typed letters were reset by a backspace or a word deletion.
+    Corrected,
+    /// There was nothing to correct, or the test is over and input is locked.
+    Ignored,
+    /// The text was completed or the time ran out, the sample is recorded.
+    Finished,
+}
+
+/// A practice session independent of any front end: tracks the text, the cursor and the statistic.
+pub struct TypingSession {
+    source: Box<dyn TextSource>,
+    clock: Box<dyn Clock>,
+    statistic: TypingStatistic,
+    error_policy: ErrorPolicy,
+    mode: TestMode,
+    /// When the first key of the running test was pressed.
+    started: Option<Instant>,
+    /// Final state of a test that is over, input stays locked until it is restarted.
+    result: Option<TypingState>,
+    /// Texts of the running test as they came from the source, used to retry it.
+    served: Vec<String>,
+    /// Texts of the test being retried still to append, the last one comes next.
+    replay: Vec<String>,
+    /// Seed the source was given for the running test.
+    seed: u64,
+    /// Seed every test is generated from, instead of a random one.
+    fixed_seed: Option<u64>,
+    text: Vec<KeyLetter>,
+    cursor: usize,
+}
+
+impl TypingSession {
+    pub fn new(source: Box<dyn TextSource>, clock: Box<dyn Clock>, statistic: TypingStatistic) -> Self {
+        let mut session = TypingSession {
+            source,
+            clock,
+            statistic,
+            error_policy: ErrorPolicy::default(),
+            mode: TestMode::default(),
+            started: None,
+            result: None,
+            served: vec![],
+            replay: vec![],
+            seed: 0,
+            fixed_seed: None,
+            text: vec![],
+            cursor: 0,
+        };
+        session.reseed();
+        session.next_text();
+        session
+    }
+
+    pub fn text(&self) -> &Vec<KeyLetter> {
+        &self.text
+    }
+
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    pub fn statistic(&self) -> &TypingStatistic {
+        &self.statistic
+    }
+
+    pub fn current_state(&self) -> TypingState {
+        match &self.result {
+            Some(result) => result.clone(),
+            None => self.statistic.get_current_state(self.clock.now())
+        }
+    }
+
+    /// Label of the source, telling when it has nothing to practice.
+    pub fn label(&self) -> String {
+        let label = self.source.label();
+        match (self.text.is_empty(), label.is_empty()) {
+            (false, _) => label,
+            (true, true) => "No text to practice".to_string(),
+            (true, false) => format!("{}, no text to practice", label),
+        }
+    }
+
+    /// Whether the source came up with no text, input is ignored until another source or other
+    /// settings give one.
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    /// See `TextSource::attribution`.
+    pub fn attribution(&self) -> Option<String> {
+        self.source.attribution()
+    }
+
+    pub fn error_policy(&self) -> ErrorPolicy {
+        self.error_policy
+    }
+
+    pub fn set_error_policy(&mut self, error_policy: ErrorPolicy) {
+        self.error_policy = error_policy;
+    }
+
+    pub fn mode(&self) -> TestMode {
+        self.mode
+    }
+
+    /// Practices texts of another source from now on, starting over with a new text.
+    pub fn set_source(&mut self, source: Box<dyn TextSource>) {
+        self.source = source;
+        self.set_mode(self.mode);
+    }
+
+    /// Switches the mode and starts over with a new text.
+    pub fn set_mode(&mut self, mode: TestMode) {
+        self.mode = mode;
+        self.source.set_word_count(match mode {
+            TestMode::Words(words) => words,
+            _ => DEFAULT_WORDS
+        });
+        self.restart();
+    }
+
+    /// Whether the test is over and waits for a restart.
+    pub fn is_locked(&self) -> bool {
+        self.result.is_some()
+    }
+
+    /// Time left in a timed test, `None` in other modes.
+    pub fn remaining(&self) -> Option<Duration> {
+        match self.mode {
+            TestMode::Timed(seconds) => {
+                let limit = Duration::from_secs(seconds);
+                let elapsed = match (&self.result, self.started) {
+                    (Some(_), _) => limit,
+                    (None, Some(started)) => self.clock.now().duration_since(started),
+                    (None, None) => Duration::from_secs(0),
+                };
+                Some(limit.checked_sub(elapsed).unwrap_or_default())
+            }
+            _ => None
+        }
+    }
+
+    /// Ends a timed test whose time ran out, front ends call it periodically.
+    pub fn tick(&mut self) -> Outcome {
+        match self.deadline() {
+            Some(deadline) if self.result.is_none() && self.clock.now() >= deadline => {
+                // Time ran out in the middle of the text, the run doesn't compare to complete ones.
+                self.statistic.set_text_id(None);
+                self.result = Some(self.statistic.finish_sample(deadline));
+                Outcome::Finished
+            }
+            _ => Outcome::Ignored
+        }
+    }
+
+    /// Drops the running sample and starts over with a new text.
+    pub fn restart(&mut self) {
+        self.reset();
+        self.reseed();
+        self.next_text();
+    }
+
+    /// Drops the running sample and starts over with the same text. A timed test goes on with
+    /// the texts it went on with before, the source isn't asked again until they run out.
+    pub fn retry(&mut self) {
+        self.reset();
+        let mut served = std::mem::take(&mut self.served);
+        if served.is_empty() {
+            return;
+        }
+        let text = served.remove(0);
+        self.set_text(&text);
+        served.reverse();
+        self.replay = served;
+    }
+
+    /// Seed of the running test, sharing it lets someone else practice the same text with the
+    /// same word list and settings.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Generates every following test from `seed`, `None` picks a random one each time.
+    pub fn set_seed(&mut self, seed: Option<u64>) {
+        self.fixed_seed = seed;
+        self.restart();
+    }
+
+    fn reset(&mut self) {
+        if !self.statistic.is_finished() {
+            self.statistic.cancel_sample();
+        }
+        self.started = None;
+        self.result = None;
+    }
+
+    /// Types `input`, which may hold several letters when it comes from a dead key or an input
+    /// method. Stops at the first letter that finishes the sample.
+    pub fn press(&mut self, input: &str) -> Outcome {
+        let mut outcome = Outcome::Ignored;
+        for letter in letters(input) {
+            outcome = self.press_letter(&letter);
+            if outcome == Outcome::Finished {
+                break;
+            }
+        }
+        outcome
+    }
+
+    /// Ends a test whose time ran out, returns the outcome of input that can't be handled anymore.
+    fn lock_outcome(&mut self) -> Option<Outcome> {
+        if self.tick() == Outcome::Finished {
+            return Some(Outcome::Finished);
+        }
+        if self.is_locked() {
+            return Some(Outcome::Ignored);
+        }
+        None
+    }
+
+    fn press_letter(&mut self, key: &str) -> Outcome {
+        if let Some(outcome) = self.lock_outcome() {
+            return outcome;
+        }
+
+        let cursor = self.cursor;
+        if cursor >= self.text.len() {
+            return Outcome::Ignored;
+        }
+
+        let now = self.clock.now();
+        if self.statistic.is_finished() {
+            self.statistic.start_sample(now);
+            self.started = Some(now);
+        }
+
+        let letter = self.text[cursor].clone();
+        // A sequence only counts when all of it up to this letter was typed correctly.
+        let typed_before = &self.text[cursor.saturating_sub(2)..cursor];
+        let correct = typed_before.iter().rev().take_while(|l| l.pressed == Pressed::Pressed).count();
+        let preceding: String = typed_before[typed_before.len() - correct..].iter()
+            .map(|l| l.character.as_str())
+            .collect();
+        let repeated = !self.text[cursor].count(letter.character == key);
+        if letter.character != key {
+            self.statistic.key_pressed(now, &preceding, &letter.character, key, repeated);
+            self.text[cursor].pressed = Pressed::WrongPressed;
+            self.text[cursor].typed = Some(key.to_string());
+            if self.error_policy == ErrorPolicy::Skip && self.advance(now) {
+                return Outcome::Finished;
+            }
+            return Outcome::Wrong;
+        }
+
+        self.text[cursor].pressed = Pressed::Pressed;
+        self.text[cursor].typed = None;
+        self.statistic.key_pressed(now, &preceding, &letter.character, key, repeated);
+        if self.advance(now) {
+            return Outcome::Finished;
+        }
+        Outcome::Correct
+    }
+
+    /// Moves the cursor on and handles the end of the text, returns whether a sample was finished.
+    fn advance(&mut self, now: Instant) -> bool {
+        self.cursor += 1;
+        if self.cursor < self.text.len() {
+            return false;
+        }
+        self.source.text_completed();
+        match self.mode {
+            TestMode::Practice => {
+                self.statistic.finish_sample(now);
+                self.started = None;
+                self.next_text();
+                true
+            }
+            // A timed test never runs out of text.
+            TestMode::Timed(_) => {
+                self.append_text();
+                false
+            }
+            TestMode::Words(_) => {
+                self.result = Some(self.statistic.finish_sample(now));
+                true
+            }
+        }
+    }
+
+    fn deadline(&self) -> Option<Instant> {
+        match self.mode {
+            TestMode::Timed(seconds) => self.started.map(|started| started + Duration::from_secs(seconds)),
+            _ => None
+        }
+    }
+
+    /// Clears the wrong mark under the cursor or steps back over the previous letter.
+    pub fn backspace(&mut self) -> Outcome {
+        if let Some(outcome) = self.lock_outcome() {
+            return outcome;
+        }
+        if self.current_is_wrong() {
+            self.text[self.cursor].clear();
+        } else if self.cursor > 0 {
+            self.cursor -= 1;
+            self.text[self.cursor].clear();
+        } else {
+            return Outcome::Ignored;
+        }
+        self.statistic.correction_pressed(self.clock.now());
+        Outcome::Corrected
+    }
+
+    /// Steps back to the start of the current word, or of the previous one when at a word start.
+    pub fn delete_word(&mut self) -> Outcome {
+        if let Some(outcome) = self.lock_outcome() {
+            return outcome;
+        }
+        let end = if self.current_is_wrong() { self.cursor + 1 } else { self.cursor };
+        let mut start = self.cursor;
+        while start > 0 && self.text[start - 1].is_whitespace() {
+            start -= 1;
+        }
+        while start > 0 && !self.text[start - 1].is_whitespace() {
+            start -= 1;
+        }
+        if start == end {
+            return Outcome::Ignored;
+        }
+
+        for letter in &mut self.text[start..end] {
+            letter.clear();
+        }
+        self.cursor = start;
+        self.statistic.correction_pressed(self.clock.now());
+        Outcome::Corrected
+    }
+
+    /// Whether the letter under the cursor holds an error that has to be corrected first.
+    fn current_is_wrong(&self) -> bool {
+        self.text.get(self.cursor).is_some_and(|l| l.pressed == Pressed::WrongPressed)
+    }
+
+    /// Replaces the typed text with a separator and a new text, keeping the letters not typed yet.
+    /// Multi-line texts like code are separated by a line break, others by a space.
+    fn append_text(&mut self) {
+        self.text.drain(..self.cursor);
+        self.cursor = 0;
+        let text = match self.replay.pop() {
+            Some(text) => text,
+            None => self.source.next_text(&self.statistic),
+        };
+        self.served.push(text.clone());
+        // The sample spans several texts now, it can't be compared to another run of one of them.
+        self.statistic.set_text_id(None);
+        let separator = if text.contains('\n') { "\n" } else { " " };
+        self.text.push(KeyLetter::new(separator.to_string(), Pressed::NotPressed));
+        self.text.extend(letters(&text).into_iter().map(|l| KeyLetter::new(l, Pressed::NotPressed)));
+    }
+
+    /// Gives the source the seed of a new test. Texts following each other in practice mode
+    /// come from the same seed, so a fixed seed doesn't serve one text over and over.
+    fn reseed(&mut self) {
+        self.seed = self.fixed_seed.unwrap_or_else(|| rand::thread_rng().gen_range(0, MAX_SEED));
+        self.source.reseed(self.seed);
+    }
+
+    /// Moves on to the next text of the source.
+    fn next_text(&mut self) {
+        let mut text = String::new();
+        for _ in 0..TEXT_ATTEMPTS {
+            text = self.source.next_text(&self.statistic);
+            if !text.is_empty() {
+                break;
+            }
+        }
+        self.replay.clear();
+        self.set_text(&text);
+    }
+
+    fn set_text(&mut self, text: &str) {
+        self.served = vec![text.to_string()];
+        self.statistic.set_text_id(self.source.text_id());
+        self.text = letters(text).into_iter()
+            .map(|l| KeyLetter::new(l, Pressed::NotPressed))
+            .collect();
+        self.cursor = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(text: &str) -> (TypingSession, Rc<ManualClock>) {
+        let clock = Rc::new(ManualClock::new());
+        let text = text.to_string();
+        let source = move |_: &TypingStatistic| text.clone();
+        (TypingSession::new(Box::new(source), Box::new(clock.clone()), TypingStatistic::new()), clock)
+    }
+
+    fn pressed(session: &TypingSession) -> Vec<Pressed> {
+        session.text().iter().map(|l| l.pressed).collect()
+    }
+
+    #[test]
+    fn empty_texts_are_skipped_or_reported() {
+        let mut texts = vec!["ab".to_string(), String::new()];
+        let source = move |_: &TypingStatistic| texts.pop().unwrap_or_default();
+        let mut session = TypingSession::new(Box::new(source), Box::new(ManualClock::new()), TypingStatistic::new());
+        assert_eq!(session.text().len(), 2);
+        assert_eq!(session.press("ab"), Outcome::Finished);
+        assert!(session.is_empty());
+        assert_eq!(session.label(), "No text to practice");
+        assert_eq!(session.press("a"), Outcome::Ignored);
+    }
+
+    #[test]
+    fn retry_replays_the_texts_without_asking_the_source() {
+        let calls = Rc::new(Cell::new(0));
+        let counter = calls.clone();
+        let source = move |_: &TypingStatistic| {
+            counter.set(counter.get() + 1);
+            format!("t{}", counter.get())
+        };
+        let mut session = TypingSession::new(Box::new(source), Box::new(ManualClock::new()), TypingStatistic::new());
+        session.set_mode(TestMode::Timed(15));
+        session.press("t2");
+        session.press(" t3");
+        assert_eq!(calls.get(), 4);
+
+        session.retry();
+        assert_eq!(session.text().iter().map(|l| l.character.as_str()).collect::<String>(), "t2");
+        session.press("t2 t3 t4");
+        assert_eq!(calls.get(), 5);
+        assert_eq!(session.text().iter().map(|l| l.character.as_str()).collect::<String>(), " t5");
+    }
+
+    /// Serves the same text with an id, like a passage.
+    struct Quote;
+
+    impl TextSource for Quote {
+        fn next_text(&mut self, _statistic: &TypingStatistic) -> String {
+            "ab".to_string()
+        }
+
+        fn text_id(&self) -> Option<String> {
+            Some("quote".to_string())
+        }
+    }
+
+    #[test]
+    fn only_complete_runs_compare_to_the_personal_best() {
+        let clock = Rc::new(ManualClock::new());
+        let mut session = TypingSession::new(Box::new(Quote), Box::new(clock.clone()), TypingStatistic::new());
+        session.set_mode(TestMode::Words(1));
+        session.press("ab");
+        assert_eq!(session.statistic().last_sample_best().map(|b| b.attempts), Some(1));
+
+        session.set_mode(TestMode::Timed(15));
+        session.press("a");
+        clock.advance(Duration::from_secs(15));
+        assert_eq!(session.tick(), Outcome::Finished);
+        assert_eq!(session.statistic().last_sample_best(), None);
+    }
+
+    /// Serves the same text and remembers the seeds it was given.
+    struct Seeded(Rc<RefCell<Vec<u64>>>);
+
+    impl TextSource for Seeded {
+        fn next_text(&mut self, _statistic: &TypingStatistic) -> String {
+            "ab".to_string()
+        }
+
+        fn reseed(&mut self, seed: u64) {
+            self.0.borrow_mut().push(seed);
+        }
+    }
+
+    #[test]
+    fn a_fixed_seed_is_set_once_per_test() {
+        let seeds = Rc::new(RefCell::new(vec![]));
+        let source = Seeded(seeds.clone());
+        let mut session = TypingSession::new(Box::new(source), Box::new(ManualClock::new()), TypingStatistic::new());
+        session.set_seed(Some(7));
+        seeds.borrow_mut().clear();
+        session.press("abab");
+        assert!(seeds.borrow().is_empty());
+        session.restart();
+        assert_eq!(*seeds.borrow(), vec![7]);
+    }
+
+    #[test]
+    fn practice_records_a_sample_per_text() {
+        let (mut session, clock) = session("abc");
+        assert_eq!(session.press("a"), Outcome::Correct);
+        clock.advance(Duration::from_secs(30));
+        assert_eq!(session.press("b"), Outcome::Correct);
+        assert_eq!(session.current_state().speed, 4.0);
+        assert_eq!(session.press("c"), Outcome::Finished);
+        assert!(!session.is_locked());
+        assert_eq!(session.cursor(), 0);
+        // The first key of a sample has no latency to record.
+        let keys: Vec<String> = session.statistic().last_sample_keys().into_iter().map(|(k, _)| k).collect();
+        assert_eq!(keys, vec!["b", "c"]);
+    }
+
+    #[test]
+    fn must_correct_keeps_the_cursor_on_a_wrong_letter() {
+        let (mut session, _clock) = session("ab");
+        assert_eq!(session.press("x"), Outcome::Wrong);
+        assert_eq!(session.cursor(), 0);
+        assert_eq!(session.text()[0].typed.as_deref(), Some("x"));
+        assert_eq!(session.press("y"), Outcome::Wrong);
+        assert_eq!(session.backspace(), Outcome::Corrected);
+        assert_eq!(pressed(&session), vec![Pressed::NotPressed, Pressed::NotPressed]);
+        assert_eq!(session.press("a"), Outcome::Correct);
+        assert_eq!(session.cursor(), 1);
+        assert_eq!(session.current_state().errors, 1);
+        assert_eq!(session.current_state().corrections, 1);
+    }
+
+    #[test]
+    fn retyped_letters_are_counted_once() {
+        let (mut session, clock) = session("abc");
+        session.press("a");
+        session.press("x");
+        session.backspace();
+        session.backspace();
+        session.press("a");
+        session.press("x");
+        session.backspace();
+        clock.advance(Duration::from_secs(60));
+        session.press("b");
+        let state = session.current_state();
+        assert_eq!(state.speed, 2.0);
+        assert_eq!(state.errors, 1);
+        assert_eq!(state.accuracy, 200.0 / 3.0);
+        assert_eq!(state.corrections, 3);
+    }
+
+    #[test]
+    fn skip_moves_on_and_words_mode_locks_at_the_end() {
+        let (mut session, _clock) = session("ab");
+        session.set_mode(TestMode::Words(1));
+        session.set_error_policy(ErrorPolicy::Skip);
+        assert_eq!(session.press("x"), Outcome::Wrong);
+        assert_eq!(session.cursor(), 1);
+        assert_eq!(session.press("b"), Outcome::Finished);
+        assert!(session.is_locked());
+        assert_eq!(session.current_state().errors, 1);
+        assert_eq!(pressed(&session), vec![Pressed::WrongPressed, Pressed::Pressed]);
+        assert_eq!(session.press("a"), Outcome::Ignored);
+    }
+
+    #[test]
+    fn corrections_are_ignored_once_the_test_is_over() {
+        let (mut session, _clock) = session("ab");
+        session.set_mode(TestMode::Words(1));
+        assert_eq!(session.press("ab"), Outcome::Finished);
+        assert_eq!(session.backspace(), Outcome::Ignored);
+        assert_eq!(session.delete_word(), Outcome::Ignored);
+        assert_eq!(session.cursor(), 2);
+        assert_eq!(pressed(&session), vec![Pressed::Pressed, Pressed::Pressed]);
+
+        session.restart();
+        session.press("a");
+        assert_eq!(session.current_state().corrections, 0);
+    }
+
+    #[test]
+    fn delete_word_steps_back_a_word_at_a_time() {
+        let (mut session, _clock) = session("ab cd");
+        session.press("ab c");
+        assert_eq!(session.delete_word(), Outcome::Corrected);
+        assert_eq!(session.cursor(), 3);
+        assert_eq!(session.delete_word(), Outcome::Corrected);
+        assert_eq!(session.cursor(), 0);
+        assert!(pressed(&session).iter().all(|p| *p == Pressed::NotPressed));
+        assert_eq!(session.delete_word(), Outcome::Ignored);
+        assert_eq!(session.backspace(), Outcome::Ignored);
+    }
+
+    #[test]
+    fn timed_test_appends_text_and_ends_on_tick() {
+        let (mut session, clock) = session("ab");
+        session.set_mode(TestMode::Timed(15));
+        clock.advance(Duration::from_secs(60));
+        assert_eq!(session.tick(), Outcome::Ignored);
+        assert_eq!(session.remaining(), Some(Duration::from_secs(15)));
+
+        session.press("a");
+        clock.advance(Duration::from_secs(10));
+        assert_eq!(session.press("b"), Outcome::Correct);
+        assert_eq!(session.text().len(), 3);
+        assert_eq!(session.tick(), Outcome::Ignored);
+        assert_eq!(session.remaining(), Some(Duration::from_secs(5)));
+
+        clock.advance(Duration::from_secs(5));
+        assert_eq!(session.tick(), Outcome::Finished);
+        assert!(session.is_locked());
+        assert_eq!(session.remaining(), Some(Duration::from_secs(0)));
+        assert_eq!(session.press(" "), Outcome::Ignored);
+        assert_eq!(session.current_state().speed, 8.0);
+    }
+}
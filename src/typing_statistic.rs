@@ -1,50 +1,251 @@
-use std::time::Instant;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use std::collections::HashMap;
+use std::io;
 use serde::{Deserialize, Serialize};
-use serde_json::Result;
+use unicode_segmentation::UnicodeSegmentation;
 
-//#[derive(Serialize, Deserialize)]
+use crate::storage;
+
+const HISTORY_FILE: &str = "statistic.json";
+
+/// Version of the history file layout, bump it together with a migration in `StatisticHistory::migrate`.
+const SCHEMA_VERSION: u32 = 4;
+
+/// Timings a key needs before it is ranked by `TypingStatistic::slowest_keys`.
+const MIN_LATENCY_COUNT: usize = 5;
+/// Occurrences a sequence needs before it is ranked as slow or error prone.
+const MIN_SEQUENCE_COUNT: usize = 5;
+
+#[derive(Serialize, Deserialize)]
+struct StatisticHistory{
+    version: u32,
+    samples: Vec<SampleStatistic>
+}
+
+impl StatisticHistory{
+    fn migrate(mut self) -> Option<StatisticHistory>{
+        if self.version == 1 {
+            // Version 1 recorded the time since the start of the sample instead of the latency of a key.
+            for sample in &mut self.samples {
+                sample.key_timings.clear();
+            }
+            self.version = 2;
+        }
+        if self.version == 2 {
+            // Version 2 counted errors on the key typed instead of the expected one. They are
+            // recounted from the confusions, samples without any lose their key errors like
+            // version 1 timings, `errors` still holds their total.
+            for sample in &mut self.samples {
+                sample.key_errors = sample.confusions.iter()
+                    .map(|(expected, typed)| (expected.clone(), typed.values().sum()))
+                    .collect();
+            }
+            self.version = 3;
+        }
+        if self.version == 3 {
+            // Version 3 didn't count the presses of a sequence, the timed and the wrong ones are
+            // as close as it gets.
+            for sample in &mut self.samples {
+                let mut presses: HashMap<String, usize> = sample.sequence_errors.clone();
+                for (sequence, timings) in &sample.sequence_timings {
+                    *presses.entry(sequence.clone()).or_insert(0) += timings.len();
+                }
+                sample.sequence_presses = presses;
+            }
+            self.version = 4;
+        }
+        match self.version {
+            SCHEMA_VERSION => Some(self),
+            _ => None
+        }
+    }
+}
+
+/// Reads a history file of any known version. The version is checked before the samples, so a
+/// layout written by a newer version is reported as such instead of as broken.
+fn parse_history(contents: &str) -> Result<StatisticHistory, String>{
+    let value: serde_json::Value = serde_json::from_str(contents)
+        .map_err(|e| format!("statistic history is corrupt: {}", e))?;
+    let version = value.get("version").and_then(|v| v.as_u64())
+        .ok_or_else(|| "statistic history has no version".to_string())?;
+    if version > SCHEMA_VERSION as u64 {
+        return Err(format!("statistic history has unsupported version {}", version));
+    }
+    let history: StatisticHistory = serde_json::from_value(value)
+        .map_err(|e| format!("statistic history is corrupt: {}", e))?;
+    history.migrate().ok_or_else(|| format!("statistic history has unsupported version {}", version))
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 struct SampleStatistic{
-    start_time: Instant,
-    key_timings: HashMap<char, Vec<usize>>,
-    key_errors: HashMap<char, usize>,
+    /// Wall-clock start of the sample in milliseconds since the unix epoch.
+    start_time: u64,
+    /// Milliseconds between the previous keystroke and a correct press of the key.
+    key_timings: HashMap<String, Vec<usize>>,
+    /// Wrong presses keyed by the expected key.
+    key_errors: HashMap<String, usize>,
+    /// Same as `key_timings`, keyed by the bigram and the trigram of the text ending with the key.
+    #[serde(default)]
+    sequence_timings: HashMap<String, Vec<usize>>,
+    /// Wrong presses keyed by the bigram and the trigram of the text ending with the expected key.
+    #[serde(default)]
+    sequence_errors: HashMap<String, usize>,
+    /// Presses of the last key of a sequence after the rest of it was typed correctly.
+    #[serde(default)]
+    sequence_presses: HashMap<String, usize>,
+    /// Wrong presses keyed by the expected letter, then by the letter typed instead.
+    #[serde(default)]
+    confusions: HashMap<String, HashMap<String, usize>>,
     length: usize,
     time: usize,
-    errors: usize
+    errors: usize,
+    #[serde(default)]
+    corrections: usize,
+    /// Text the sample was typed on, for texts that can come up again like passages.
+    #[serde(default)]
+    text_id: Option<String>
 }
 
 impl SampleStatistic{
-    pub fn new(start_time: Instant,
-               key_timings: HashMap<char, Vec<usize>>,
-               key_errors: HashMap<char, usize>,
-               length: usize,
-               time: usize,
-               errors: usize) -> SampleStatistic{
-        SampleStatistic{
-            start_time,
-            key_timings,
-            key_errors,
-            length,
-            time,
-            errors
+    /// Same as `TypingState::net_wpm` at the end of the sample.
+    fn net_wpm(&self) -> f64{
+        let minutes = self.time as f64 / 60_000_000.0;
+        if minutes <= 0.0 {
+            return 0.0;
         }
+        let gross_wpm = (self.length + self.errors) as f64 / WORD_LENGTH / minutes;
+        (gross_wpm - self.errors as f64 / minutes).max(0.0)
     }
 }
 
+/// Characters counted as one word by the words-per-minute metrics.
+const WORD_LENGTH: f64 = 5.0;
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct TypingState{
+    /// Correct characters per minute.
     pub speed: f64,
-    pub errors: usize
+    /// Words per minute counting every typed character, correct or not.
+    pub gross_wpm: f64,
+    /// Gross words per minute minus the errors made per minute.
+    pub net_wpm: f64,
+    /// Percentage of correct keystrokes.
+    pub accuracy: f64,
+    /// Percentage derived from the variance of the intervals between keystrokes, 100 is perfectly even typing.
+    pub consistency: f64,
+    pub errors: usize,
+    pub corrections: usize
+}
+
+/// Distribution of the time between a keystroke and the one before it, in milliseconds.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Latency{
+    pub count: usize,
+    pub mean: f64,
+    pub median: f64,
+    pub p90: f64
+}
+
+impl Latency{
+    fn from_timings(mut timings: Vec<usize>) -> Option<Latency>{
+        if timings.is_empty() {
+            return None;
+        }
+        timings.sort();
+        let count = timings.len();
+        let percentile = |p: f64| timings[((p * count as f64).ceil() as usize).max(1) - 1] as f64;
+        Some(Latency{
+            count,
+            mean: timings.iter().sum::<usize>() as f64 / count as f64,
+            median: percentile(0.5),
+            p90: percentile(0.9)
+        })
+    }
+}
+
+/// Speed and accuracy of a bigram or trigram over all recorded samples.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SequenceStatistic{
+    pub sequence: String,
+    /// Times the sequence was typed, correctly or not.
+    pub count: usize,
+    pub errors: usize,
+    /// Latency of the last key of the sequence, `None` when it was never typed right in a row.
+    pub latency: Option<Latency>
+}
+
+impl SequenceStatistic{
+    pub fn error_rate(&self) -> f64{
+        if self.count == 0 {
+            return 0.0;
+        }
+        self.errors as f64 / self.count as f64
+    }
+}
+
+/// Fastest run on a text over all recorded samples.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PersonalBest{
+    pub net_wpm: f64,
+    /// Recorded samples of the text.
+    pub attempts: usize,
+    /// Whether the last sample set the best, beating at least one earlier attempt.
+    pub is_new: bool
+}
+
+/// A letter typed in place of another one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Substitution{
+    pub expected: String,
+    pub typed: String,
+    pub count: usize
+}
+
+/// Speed and accuracy of a single key over all recorded samples.
+pub struct KeyPerformance{
+    pub presses: usize,
+    pub errors: usize,
+    /// Mean time per press in milliseconds.
+    pub mean_time: f64
+}
+
+impl KeyPerformance{
+    pub fn error_rate(&self) -> f64{
+        if self.presses + self.errors == 0 {
+            return 0.0;
+        }
+        self.errors as f64 / (self.presses + self.errors) as f64
+    }
 }
 
 pub struct TypingStatistic{
     samples: Vec<SampleStatistic>,
-    key_timings: HashMap<char, Vec<usize>>,
-    key_errors: HashMap<char, usize>,
-    start_sample: Instant,
-    start_key: Instant,
+    key_timings: HashMap<String, Vec<usize>>,
+    key_errors: HashMap<String, usize>,
+    sequence_timings: HashMap<String, Vec<usize>>,
+    sequence_errors: HashMap<String, usize>,
+    sequence_presses: HashMap<String, usize>,
+    confusions: HashMap<String, HashMap<String, usize>>,
+    text_id: Option<String>,
+    /// When the running sample started, `None` between samples.
+    start_sample: Option<Instant>,
+    last_key: Option<Instant>,
+    /// Whether the previous keystroke was a correct press, so the next latency belongs to a sequence.
+    in_sequence: bool,
+    /// Milliseconds between consecutive keystrokes of the current sample.
+    key_intervals: Vec<f64>,
     key_count: usize,
     errors_count: usize,
+    corrections_count: usize,
     finished: bool,
+    /// Cleared when the history on disk was written by an unknown version and must not be overwritten.
+    persistent: bool,
+}
+
+impl Default for TypingStatistic{
+    fn default() -> Self{
+        TypingStatistic::new()
+    }
 }
 
 impl TypingStatistic{
@@ -53,60 +254,519 @@ impl TypingStatistic{
             samples: vec![],
             key_timings: HashMap::new(),
             key_errors: HashMap::new(),
-            start_sample: Instant::now(),
-            start_key: Instant::now(),
+            sequence_timings: HashMap::new(),
+            sequence_errors: HashMap::new(),
+            sequence_presses: HashMap::new(),
+            confusions: HashMap::new(),
+            text_id: None,
+            start_sample: None,
+            last_key: None,
+            in_sequence: false,
+            key_intervals: vec![],
             key_count: 0,
             errors_count: 0,
+            corrections_count: 0,
             finished: true,
+            persistent: true,
+        }
+    }
+
+    /// Restores the samples recorded by previous runs. A history that can't be read is left
+    /// untouched on disk: nothing is saved over it during this run.
+    pub fn load() -> Self{
+        let mut statistic = TypingStatistic::new();
+        match storage::read(HISTORY_FILE) {
+            Ok(Some(contents)) => match parse_history(&contents) {
+                Ok(history) => statistic.samples = history.samples,
+                Err(e) => {
+                    println!("{}, the statistic will not be saved", e);
+                    statistic.persistent = false;
+                }
+            },
+            Ok(None) => {}
+            Err(e) => {
+                println!("failed to read statistic history: {}, the statistic will not be saved", e);
+                statistic.persistent = false;
+            }
         }
+        statistic
     }
 
-    pub fn get_current_state(&self) -> TypingState{
+    pub fn save(&self) -> io::Result<()>{
+        if !self.persistent {
+            return Ok(());
+        }
+        storage::save(HISTORY_FILE, &StatisticHistory{
+            version: SCHEMA_VERSION,
+            samples: self.samples.clone()
+        })
+    }
+
+    pub fn get_current_state(&self, now: Instant) -> TypingState{
+        let minutes = match self.start_sample {
+            Some(start) if !self.finished => now.duration_since(start).as_secs_f64() / 60.0,
+            _ => 0.0
+        };
+        let typed = self.key_count + self.errors_count;
+        let per_minute = |count: f64| if minutes > 0.0 { count / minutes } else { 0.0 };
+
+        let gross_wpm = per_minute(typed as f64 / WORD_LENGTH);
         TypingState{
-            speed: self.key_count as f64 / (self.start_sample.elapsed().as_secs() as f64 / 60.0),
-            errors: self.errors_count
+            speed: per_minute(self.key_count as f64),
+            gross_wpm,
+            net_wpm: (gross_wpm - per_minute(self.errors_count as f64)).max(0.0),
+            accuracy: if typed == 0 {
+                100.0
+            } else {
+                self.key_count as f64 * 100.0 / typed as f64
+            },
+            consistency: consistency(&self.key_intervals),
+            errors: self.errors_count,
+            corrections: self.corrections_count
+        }
+    }
+
+    pub fn key_performance(&self, key: &str) -> KeyPerformance{
+        let timings = self.key_timings_of(key);
+        let errors = self.samples.iter()
+            .map(|s| &s.key_errors)
+            .chain(std::iter::once(&self.key_errors))
+            .filter_map(|e| e.get(key))
+            .sum();
+
+        KeyPerformance{
+            presses: timings.len(),
+            errors,
+            mean_time: if timings.is_empty() {
+                0.0
+            } else {
+                timings.iter().sum::<usize>() as f64 / timings.len() as f64
+            }
+        }
+    }
+
+    /// Latency of correct presses of `key` over all recorded samples.
+    pub fn key_latency(&self, key: &str) -> Option<Latency>{
+        Latency::from_timings(self.key_timings_of(key))
+    }
+
+    /// Latency of correct presses of `key` right after `previous`.
+    pub fn sequence_latency(&self, previous: &str, key: &str) -> Option<Latency>{
+        let sequence = format!("{}{}", previous, key);
+        let timings = self.samples.iter()
+            .map(|s| &s.sequence_timings)
+            .chain(std::iter::once(&self.sequence_timings))
+            .filter_map(|t| t.get(&sequence))
+            .flat_map(|t| t.iter().cloned())
+            .collect();
+        Latency::from_timings(timings)
+    }
+
+    /// Statistic of every bigram (`length` 2) or trigram (`length` 3) typed so far.
+    pub fn sequence_statistics(&self, length: usize) -> Vec<SequenceStatistic>{
+        let mut timings: HashMap<&String, Vec<usize>> = HashMap::new();
+        let mut errors: HashMap<&String, usize> = HashMap::new();
+        let mut presses: HashMap<&String, usize> = HashMap::new();
+        for (sample_timings, sample_errors, sample_presses) in self.samples.iter()
+            .map(|s| (&s.sequence_timings, &s.sequence_errors, &s.sequence_presses))
+            .chain(std::iter::once((&self.sequence_timings, &self.sequence_errors, &self.sequence_presses))) {
+            for (sequence, t) in sample_timings {
+                timings.entry(sequence).or_insert(vec![]).extend(t);
+            }
+            for (sequence, e) in sample_errors {
+                *errors.entry(sequence).or_insert(0) += e;
+            }
+            for (sequence, p) in sample_presses {
+                *presses.entry(sequence).or_insert(0) += p;
+            }
+        }
+
+        let mut sequences: Vec<&String> = presses.keys().cloned()
+            .filter(|sequence| sequence.graphemes(true).count() == length)
+            .collect();
+        sequences.sort();
+        sequences.dedup();
+
+        sequences.into_iter()
+            .map(|sequence| {
+                let sequence_timings = timings.remove(sequence).unwrap_or_default();
+                let sequence_errors = errors.get(sequence).cloned().unwrap_or(0);
+                SequenceStatistic{
+                    sequence: sequence.clone(),
+                    count: presses.get(sequence).cloned().unwrap_or(0),
+                    errors: sequence_errors,
+                    latency: Latency::from_timings(sequence_timings)
+                }
+            })
+            .collect()
+    }
+
+    /// Up to `n` sequences of `length` characters with the highest mean latency, slowest first.
+    pub fn slowest_sequences(&self, length: usize, n: usize) -> Vec<SequenceStatistic>{
+        let mut sequences: Vec<SequenceStatistic> = self.sequence_statistics(length).into_iter()
+            .filter(|s| s.latency.as_ref().is_some_and(|l| l.count >= MIN_SEQUENCE_COUNT))
+            .collect();
+        sequences.sort_by(|a, b| {
+            let mean = |s: &SequenceStatistic| s.latency.as_ref().map_or(0.0, |l| l.mean);
+            mean(b).partial_cmp(&mean(a)).unwrap()
+        });
+        sequences.truncate(n);
+        sequences
+    }
+
+    /// Up to `n` sequences of `length` characters with the highest error rate, worst first.
+    pub fn error_prone_sequences(&self, length: usize, n: usize) -> Vec<SequenceStatistic>{
+        let mut sequences: Vec<SequenceStatistic> = self.sequence_statistics(length).into_iter()
+            .filter(|s| s.errors > 0 && s.count >= MIN_SEQUENCE_COUNT)
+            .collect();
+        sequences.sort_by(|a, b| b.error_rate().partial_cmp(&a.error_rate()).unwrap());
+        sequences.truncate(n);
+        sequences
+    }
+
+    /// Up to `n` keys with the highest mean latency, slowest first.
+    pub fn slowest_keys(&self, n: usize) -> Vec<(String, Latency)>{
+        let mut keys: Vec<&String> = self.samples.iter()
+            .flat_map(|s| s.key_timings.keys())
+            .chain(self.key_timings.keys())
+            .collect();
+        keys.sort();
+        keys.dedup();
+
+        let mut latencies: Vec<(String, Latency)> = keys.into_iter()
+            .filter_map(|key| self.key_latency(key).map(|latency| (key.clone(), latency)))
+            .filter(|(_, latency)| latency.count >= MIN_LATENCY_COUNT)
+            .collect();
+        latencies.sort_by(|a, b| b.1.mean.partial_cmp(&a.1.mean).unwrap());
+        latencies.truncate(n);
+        latencies
+    }
+
+    /// Up to `n` keys with the highest error rate, worst first.
+    pub fn error_prone_keys(&self, n: usize) -> Vec<(String, KeyPerformance)>{
+        let mut keys: Vec<&String> = self.samples.iter()
+            .flat_map(|s| s.key_errors.keys())
+            .chain(self.key_errors.keys())
+            .collect();
+        keys.sort();
+        keys.dedup();
+
+        let mut performance: Vec<(String, KeyPerformance)> = keys.into_iter()
+            .map(|key| (key.clone(), self.key_performance(key)))
+            .filter(|(_, p)| p.errors > 0 && p.presses + p.errors >= MIN_LATENCY_COUNT)
+            .collect();
+        performance.sort_by(|a, b| b.1.error_rate().partial_cmp(&a.1.error_rate()).unwrap());
+        performance.truncate(n);
+        performance
+    }
+
+    /// Performance of every key typed in the last recorded sample, slowest first.
+    pub fn last_sample_keys(&self) -> Vec<(String, KeyPerformance)>{
+        let sample = match self.samples.last() {
+            Some(sample) => sample,
+            None => return vec![]
+        };
+        let mut keys: Vec<&String> = sample.key_timings.keys().chain(sample.key_errors.keys()).collect();
+        keys.sort();
+        keys.dedup();
+
+        let mut performance: Vec<(String, KeyPerformance)> = keys.into_iter()
+            .map(|key| {
+                let timings = sample.key_timings.get(key).map_or(&[][..], |t| &t[..]);
+                (key.clone(), KeyPerformance{
+                    presses: timings.len(),
+                    errors: sample.key_errors.get(key).cloned().unwrap_or(0),
+                    mean_time: if timings.is_empty() {
+                        0.0
+                    } else {
+                        timings.iter().sum::<usize>() as f64 / timings.len() as f64
+                    }
+                })
+            })
+            .collect();
+        performance.sort_by(|a, b| b.1.mean_time.partial_cmp(&a.1.mean_time).unwrap());
+        performance
+    }
+
+    /// How often every letter was mistyped as another one over all recorded samples, keyed by
+    /// the expected letter, then by the letter typed instead.
+    pub fn confusion_matrix(&self) -> HashMap<String, HashMap<String, usize>>{
+        let mut matrix: HashMap<String, HashMap<String, usize>> = HashMap::new();
+        for confusions in self.samples.iter().map(|s| &s.confusions).chain(std::iter::once(&self.confusions)) {
+            for (expected, typed) in confusions {
+                let row = matrix.entry(expected.clone()).or_default();
+                for (typed, count) in typed {
+                    *row.entry(typed.clone()).or_insert(0) += count;
+                }
+            }
         }
+        matrix
+    }
+
+    /// Up to `n` substitutions made most often over all recorded samples, most frequent first.
+    pub fn common_substitutions(&self, n: usize) -> Vec<Substitution>{
+        let mut substitutions: Vec<Substitution> = self.confusion_matrix().into_iter()
+            .flat_map(|(expected, typed)| typed.into_iter().map(move |(typed, count)| Substitution{
+                expected: expected.clone(),
+                typed,
+                count
+            }))
+            .collect();
+        substitutions.sort_by(|a, b| b.count.cmp(&a.count)
+            .then_with(|| a.expected.cmp(&b.expected))
+            .then_with(|| a.typed.cmp(&b.typed)));
+        substitutions.truncate(n);
+        substitutions
+    }
+
+    /// Best run on the text of the last recorded sample, `None` if it wasn't typed on a text with
+    /// an id, see `set_text_id`.
+    pub fn last_sample_best(&self) -> Option<PersonalBest>{
+        let last = self.samples.last()?;
+        let text_id = last.text_id.as_ref()?;
+        let runs: Vec<f64> = self.samples.iter()
+            .filter(|s| s.text_id.as_ref() == Some(text_id))
+            .map(|s| s.net_wpm())
+            .collect();
+        let (last_wpm, previous) = runs.split_last()?;
+        let previous_best = previous.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+        Some(PersonalBest{
+            net_wpm: last_wpm.max(previous_best),
+            attempts: runs.len(),
+            is_new: !previous.is_empty() && *last_wpm > previous_best
+        })
+    }
+
+    fn key_timings_of(&self, key: &str) -> Vec<usize>{
+        self.samples.iter()
+            .map(|s| &s.key_timings)
+            .chain(std::iter::once(&self.key_timings))
+            .filter_map(|t| t.get(key))
+            .flat_map(|t| t.iter().cloned())
+            .collect()
     }
 
     pub fn is_finished(&self) -> bool{
         self.finished
     }
 
-    pub fn start_sample(&mut self){
-        self.start_sample = Instant::now();
+    /// Identifies the text typed from now on, `None` for texts that won't come up again.
+    pub fn set_text_id(&mut self, text_id: Option<String>){
+        self.text_id = text_id;
+    }
+
+    pub fn start_sample(&mut self, now: Instant){
+        self.start_sample = Some(now);
         self.finished = false;
     }
 
-    pub fn key_pressed(&mut self, key: char, correct: bool){
-        let time = self.start_key.elapsed().as_millis();
-        if correct {
-            let timings = self.key_timings.entry(key).or_insert(vec![]);
-            timings.push(time as usize);
+    /// Records a press of `typed` where `expected` was due, `preceding` holds the letters right
+    /// before it that were typed correctly, up to two. Letters are grapheme clusters, composed
+    /// input counts as one press.
+    /// A `repeated` press is one of the same kind as a press of the letter that was already
+    /// counted before it was corrected, it only adds to the rhythm of the sample.
+    pub fn key_pressed(&mut self, now: Instant, preceding: &str, expected: &str, typed: &str, repeated: bool){
+        // The first keystroke of a sample starts it, so it has no latency of its own.
+        let latency = self.last_key.map(|last_key| now.duration_since(last_key));
+        if let Some(latency) = latency {
+            self.key_intervals.push(latency.as_secs_f64() * 1000.0);
+        }
+        self.last_key = Some(now);
+        if repeated {
+            self.in_sequence = expected == typed;
+            return;
+        }
+
+        let sequences = sequences_ending_with(preceding, expected);
+        for sequence in &sequences {
+            *self.sequence_presses.entry(sequence.clone()).or_insert(0) += 1;
+        }
+        if expected == typed {
+            if let Some(latency) = latency {
+                let time = latency.as_millis() as usize;
+                self.key_timings.entry(typed.to_string()).or_insert(vec![]).push(time);
+                if self.in_sequence {
+                    for sequence in sequences {
+                        self.sequence_timings.entry(sequence).or_insert(vec![]).push(time);
+                    }
+                }
+            }
+            self.in_sequence = true;
             self.key_count += 1;
         } else{
-            let errors = self.key_errors.entry(key).or_insert(0);
+            let errors = self.key_errors.entry(expected.to_string()).or_insert(0);
             *errors += 1;
+            for sequence in sequences {
+                *self.sequence_errors.entry(sequence).or_insert(0) += 1;
+            }
+            *self.confusions.entry(expected.to_string()).or_default().entry(typed.to_string()).or_insert(0) += 1;
+            self.in_sequence = false;
             self.errors_count += 1;
         }
     }
 
-    pub fn finish_sample(&mut self) {
-        self.samples.push(
-            SampleStatistic::new(
-                self.start_sample,
-                self.key_timings.clone(),
-                self.key_errors.clone(),
-                self.key_count,
-                self.start_sample.elapsed().as_micros() as usize,
-                self.errors_count
-            )
-        );
+    /// Records a backspace or word deletion, kept apart from the errors it corrects.
+    pub fn correction_pressed(&mut self, now: Instant){
+        self.last_key = Some(now);
+        self.in_sequence = false;
+        self.corrections_count += 1;
+    }
+
+    /// Records the running sample and returns its final state.
+    pub fn finish_sample(&mut self, now: Instant) -> TypingState{
+        let state = self.get_current_state(now);
+        let elapsed = self.start_sample.map_or(Duration::from_secs(0), |start| now.duration_since(start));
+        self.samples.push(SampleStatistic{
+            start_time: unix_millis(SystemTime::now() - elapsed),
+            key_timings: self.key_timings.clone(),
+            key_errors: self.key_errors.clone(),
+            sequence_timings: self.sequence_timings.clone(),
+            sequence_errors: self.sequence_errors.clone(),
+            sequence_presses: self.sequence_presses.clone(),
+            confusions: self.confusions.clone(),
+            length: self.key_count,
+            time: elapsed.as_micros() as usize,
+            errors: self.errors_count,
+            corrections: self.corrections_count,
+            text_id: self.text_id.clone()
+        });
+        self.cancel_sample();
+        state
+    }
+
+    /// Drops the running sample without recording it.
+    pub fn cancel_sample(&mut self){
         self.key_timings = HashMap::new();
         self.key_errors = HashMap::new();
-        self.start_sample = Instant::now();
-        self.start_key = Instant::now();
+        self.sequence_timings = HashMap::new();
+        self.sequence_errors = HashMap::new();
+        self.sequence_presses = HashMap::new();
+        self.confusions = HashMap::new();
+        self.start_sample = None;
+        self.last_key = None;
+        self.in_sequence = false;
+        self.key_intervals = vec![];
         self.key_count = 0;
         self.errors_count = 0;
+        self.corrections_count = 0;
         self.finished = true;
     }
-}
\ No newline at end of file
+}
+
+/// Bigram and trigram made of the end of `preceding` followed by `key`.
+fn sequences_ending_with(preceding: &str, key: &str) -> Vec<String>{
+    let preceding: Vec<&str> = preceding.graphemes(true).collect();
+    (1..=2)
+        .filter(|n| *n <= preceding.len())
+        .map(|n| preceding[preceding.len() - n..].iter().cloned().chain(std::iter::once(key)).collect())
+        .collect()
+}
+
+/// Maps the coefficient of variation of `intervals` to a percentage.
+fn consistency(intervals: &[f64]) -> f64{
+    if intervals.len() < 2 {
+        return 100.0;
+    }
+    let mean = intervals.iter().sum::<f64>() / intervals.len() as f64;
+    if mean <= 0.0 {
+        return 100.0;
+    }
+    let variance = intervals.iter().map(|i| (i - mean).powi(2)).sum::<f64>() / intervals.len() as f64;
+    (100.0 * (1.0 - variance.sqrt() / mean)).max(0.0)
+}
+
+fn unix_millis(time: SystemTime) -> u64{
+    time.duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_history_rejects_corrupt_and_newer_files() {
+        assert!(parse_history("{\"version\": 3, \"samples\": [").is_err());
+        assert!(parse_history("{\"samples\": []}").is_err());
+        let newer = format!("{{\"version\": {}, \"samples\": [], \"unknown\": 1}}", SCHEMA_VERSION + 1);
+        assert!(parse_history(&newer).err().unwrap().contains("unsupported version"));
+        assert!(parse_history("{\"version\": 3, \"samples\": []}").unwrap().samples.is_empty());
+    }
+
+    #[test]
+    fn migrating_version_2_recounts_errors_on_the_expected_keys() {
+        let sample = |key_errors: &str, confusions: &str| format!(
+            "{{\"start_time\": 0, \"key_timings\": {{\"a\": [100]}}, \"key_errors\": {}, \
+              \"sequence_timings\": {{\"ba\": [100]}}, \"sequence_errors\": {{\"ab\": 1}}, \
+              \"confusions\": {}, \"length\": 2, \"time\": 1000000, \"errors\": 1}}",
+            key_errors, confusions
+        );
+        let history = format!(
+            "{{\"version\": 2, \"samples\": [{}, {}]}}",
+            sample("{\"x\": 1}", "{}"),
+            sample("{\"x\": 1}", "{\"b\": {\"x\": 1}}")
+        );
+        let history = parse_history(&history).unwrap();
+        assert_eq!(history.version, SCHEMA_VERSION);
+        for sample in &history.samples {
+            assert_eq!(sample.errors, 1);
+            assert_eq!(sample.sequence_presses.get("ab"), Some(&1));
+            assert_eq!(sample.sequence_presses.get("ba"), Some(&1));
+        }
+        assert!(history.samples[0].key_errors.is_empty());
+        assert_eq!(history.samples[1].key_errors, [("b".to_string(), 1)].iter().cloned().collect());
+    }
+
+    /// Records a sample of ten correct presses on `text_id` typed in `seconds`.
+    fn record(statistic: &mut TypingStatistic, text_id: Option<&str>, seconds: u64){
+        let start = Instant::now();
+        statistic.set_text_id(text_id.map(|id| id.to_string()));
+        statistic.start_sample(start);
+        for _ in 0..10 {
+            statistic.key_pressed(start, "", "a", "a", false);
+        }
+        statistic.finish_sample(start + Duration::from_secs(seconds));
+    }
+
+    #[test]
+    fn last_sample_best_compares_runs_on_the_same_text() {
+        let mut statistic = TypingStatistic::new();
+        record(&mut statistic, Some("quote"), 6);
+        assert_eq!(statistic.last_sample_best(), Some(PersonalBest{net_wpm: 20.0, attempts: 1, is_new: false}));
+
+        record(&mut statistic, Some("other"), 1);
+        record(&mut statistic, Some("quote"), 3);
+        assert_eq!(statistic.last_sample_best(), Some(PersonalBest{net_wpm: 40.0, attempts: 2, is_new: true}));
+
+        // A tie doesn't set a new best.
+        record(&mut statistic, Some("quote"), 3);
+        assert_eq!(statistic.last_sample_best(), Some(PersonalBest{net_wpm: 40.0, attempts: 3, is_new: false}));
+
+        record(&mut statistic, None, 1);
+        assert_eq!(statistic.last_sample_best(), None);
+    }
+
+    #[test]
+    fn sequences_count_every_press_after_a_correct_start() {
+        let mut statistic = TypingStatistic::new();
+        let start = Instant::now();
+        let at = |ms: u64| start + Duration::from_millis(ms);
+        statistic.start_sample(at(0));
+        statistic.key_pressed(at(0), "", "a", "a", false);
+        statistic.key_pressed(at(100), "a", "b", "x", false);
+        statistic.correction_pressed(at(200));
+        // Typed right after a correction, so it has no latency of the sequence.
+        statistic.key_pressed(at(300), "a", "b", "b", false);
+        statistic.key_pressed(at(400), "ab", "c", "c", false);
+        // Preceded by a wrong letter, only the bigram counts.
+        statistic.key_pressed(at(500), "c", "d", "d", false);
+
+        let bigrams = statistic.sequence_statistics(2);
+        let ab = bigrams.iter().find(|s| s.sequence == "ab").unwrap();
+        assert_eq!((ab.count, ab.errors), (2, 1));
+        assert!(ab.latency.is_none());
+        let bc = bigrams.iter().find(|s| s.sequence == "bc").unwrap();
+        assert_eq!(bc.latency.as_ref().map(|l| l.count), Some(1));
+        let trigrams: Vec<String> = statistic.sequence_statistics(3).into_iter().map(|s| s.sequence).collect();
+        assert_eq!(trigrams, vec!["abc"]);
+    }
+}
@@ -0,0 +1,145 @@
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use crate::storage;
+use crate::text_generator::TextGenerator;
+
+/// List used until the user picks another one, shipped in `res`. It holds common English words,
+/// most frequent first, with counts estimated from their rank by Zipf's law.
+pub const DEFAULT_LIST: &str = "words_frequency";
+
+/// The default list built into the program, for when `res` can't be found.
+const DEFAULT_WORDS: &str = include_str!("../res/words_frequency.txt");
+
+/// Choices for the number of words taken from the top of a list, `None` takes all of them.
+const WORD_LIMITS: [Option<usize>; 4] = [None, Some(200), Some(1000), Some(10000)];
+
+/// Directory users put their own word lists in, e.g. `~/.config/rtyping/words` on Linux.
+pub fn lists_dir() -> PathBuf {
+    storage::config_dir().join("words")
+}
+
+/// Directories searched for `<name>.txt` lists, earlier ones take precedence. Lists of other
+/// languages go in a subdirectory named after the language, like `de/<name>.txt`.
+fn search_dirs() -> Vec<PathBuf> {
+    vec![lists_dir(), storage::resource_dir()]
+}
+
+/// Subdirectories of `res` holding other resources than word lists.
+const RESOURCE_DIRS: [&str; 2] = ["markov", "snippets"];
+
+/// A word list file with one word, optionally followed by a tab and its count, per line.
+/// Lists sorted by frequency, most common words first, can be limited to their top words.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WordList {
+    /// File name without extension, prefixed by the language for lists in a language directory.
+    pub name: String,
+    pub language: Option<String>,
+    pub path: PathBuf,
+}
+
+impl WordList {
+    /// Every list found in the search directories and their language subdirectories, sorted by name.
+    pub fn discover() -> Vec<WordList> {
+        let mut lists: Vec<WordList> = vec![];
+        for dir in search_dirs() {
+            let entries = match fs::read_dir(&dir) {
+                Ok(entries) => entries,
+                Err(_) => continue,
+            };
+            for path in entries.filter_map(|e| e.ok()).map(|e| e.path()) {
+                if path.is_dir() {
+                    let language = path.file_name()
+                        .map(|n| n.to_string_lossy().into_owned())
+                        .filter(|n| !RESOURCE_DIRS.contains(&n.as_str()));
+                    if let (Some(language), Ok(entries)) = (language, fs::read_dir(&path)) {
+                        for path in entries.filter_map(|e| e.ok()).map(|e| e.path()) {
+                            add_list(&mut lists, path, Some(&language));
+                        }
+                    }
+                } else {
+                    add_list(&mut lists, path, None);
+                }
+            }
+        }
+        lists.sort_by(|a, b| a.name.cmp(&b.name));
+        lists
+    }
+
+    pub fn find(name: &str) -> Option<WordList> {
+        WordList::discover().into_iter().find(|l| l.name == name)
+    }
+
+    /// Name of the list following `name`, wrapping around after the last one.
+    pub fn next_name(name: &str) -> Option<String> {
+        let lists = WordList::discover();
+        let next = lists.iter().position(|l| l.name == name).map_or(0, |i| (i + 1) % lists.len());
+        lists.into_iter().nth(next).map(|l| l.name)
+    }
+
+    /// Builds a generator from the first `limit` words of the list, or all of them.
+    pub fn load(&self, limit: Option<usize>) -> io::Result<TextGenerator> {
+        Ok(generator(&fs::read_to_string(&self.path)?, limit))
+    }
+}
+
+fn generator(contents: &str, limit: Option<usize>) -> TextGenerator {
+    match limit {
+        Some(limit) => TextGenerator::new(
+            &contents.lines()
+                .filter(|w| !w.trim().is_empty())
+                .take(limit)
+                .collect::<Vec<&str>>()
+                .join("\n")
+        ),
+        None => TextGenerator::new(contents),
+    }
+}
+
+/// The default list as built into the program, used when the chosen list can't be loaded.
+pub fn default_list(limit: Option<usize>) -> TextGenerator {
+    generator(DEFAULT_WORDS, limit)
+}
+
+/// Adds the list at `path` unless it isn't a `.txt` file or a list of that name was found before.
+fn add_list(lists: &mut Vec<WordList>, path: PathBuf, language: Option<&str>) {
+    if path.extension().is_none_or(|e| e != "txt") {
+        return;
+    }
+    let stem = match path.file_stem() {
+        Some(stem) => stem.to_string_lossy().into_owned(),
+        None => return,
+    };
+    let name = match language {
+        Some(language) => format!("{}/{}", language, stem),
+        None => stem,
+    };
+    if !lists.iter().any(|l| l.name == name) {
+        lists.push(WordList { name, language: language.map(|l| l.to_string()), path });
+    }
+}
+
+/// Loads the list called `name`, the default list is always found.
+pub fn open(name: &str, limit: Option<usize>) -> io::Result<TextGenerator> {
+    match WordList::find(name) {
+        Some(list) => list.load(limit),
+        None if name == DEFAULT_LIST => Ok(default_list(limit)),
+        None => Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("no word list named {} in {}", name, lists_dir().display())
+        )),
+    }
+}
+
+pub fn limit_description(limit: Option<usize>) -> String {
+    match limit {
+        Some(limit) => format!("top {}", limit),
+        None => "all words".to_string(),
+    }
+}
+
+pub fn next_limit(limit: Option<usize>) -> Option<usize> {
+    let next = WORD_LIMITS.iter().position(|l| *l == limit).map_or(0, |i| i + 1);
+    WORD_LIMITS[next % WORD_LIMITS.len()]
+}
@@ -0,0 +1,246 @@
+use rand_chacha::ChaCha8Rng;
+use rand::SeedableRng;
+use serde::{Deserialize, Serialize};
+
+use crate::augmentation::Augmentation;
+use crate::lesson::Lesson;
+use crate::markov::{MarkovGenerator, MarkovLevel};
+use crate::text_generator::{GeneratorError, TextGenerator, WeakSpots};
+use crate::typing_session::TextSource;
+use crate::typing_statistic::TypingStatistic;
+
+/// Weak keys and sequences of each kind handed to adaptive generation.
+const WEAK_SPOTS: usize = 5;
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub enum GenerationMode {
+    /// Every allowed word is equally likely.
+    #[default]
+    Uniform,
+    /// Words with the user's slowest and most error-prone keys and sequences are preferred.
+    Adaptive,
+    /// Pseudo-words or phrases from a Markov model, see `MarkovGenerator`.
+    Markov(MarkovLevel),
+}
+
+impl GenerationMode {
+    pub fn description(&self) -> String {
+        match self {
+            GenerationMode::Uniform => "uniform".to_string(),
+            GenerationMode::Adaptive => "adaptive".to_string(),
+            GenerationMode::Markov(level) => format!("markov {}", level.description())
+        }
+    }
+
+    pub fn next(&self) -> GenerationMode {
+        match self {
+            GenerationMode::Uniform => GenerationMode::Adaptive,
+            GenerationMode::Adaptive => GenerationMode::Markov(MarkovLevel::Characters),
+            GenerationMode::Markov(MarkovLevel::Characters) => GenerationMode::Markov(MarkovLevel::Words),
+            GenerationMode::Markov(MarkovLevel::Words) => GenerationMode::Uniform
+        }
+    }
+}
+
+/// Generates lines of random words, restricted to the letters of a lesson when one is given.
+pub struct WordSource {
+    generator: TextGenerator,
+    /// Trained on first use, as the letter model goes through the whole word list.
+    markov: Option<MarkovGenerator>,
+    lesson: Option<Lesson>,
+    words: usize,
+    mode: GenerationMode,
+    strength: f64,
+    augmentation: Augmentation,
+    /// Why the last text couldn't be generated, shown in the label.
+    error: Option<GeneratorError>,
+    /// Last file that failed to load or save, shown in the label.
+    warning: Option<String>,
+    rng: ChaCha8Rng,
+}
+
+impl WordSource {
+    pub fn new(generator: TextGenerator, lesson: Option<Lesson>, words: usize) -> Self {
+        WordSource {
+            generator,
+            markov: None,
+            lesson,
+            words,
+            mode: GenerationMode::default(),
+            strength: 1.0,
+            augmentation: Augmentation::default(),
+            error: None,
+            warning: None,
+            rng: ChaCha8Rng::from_entropy(),
+        }
+    }
+
+    /// Switches to another word list.
+    pub fn set_generator(&mut self, generator: TextGenerator) {
+        self.generator = generator;
+        self.markov = None;
+    }
+
+    /// Restricts the words to the letters of `lesson`, `None` allows every word.
+    pub fn set_lesson(&mut self, lesson: Option<Lesson>) {
+        self.lesson = lesson;
+    }
+
+    pub fn set_augmentation(&mut self, augmentation: Augmentation) {
+        self.augmentation = augmentation;
+    }
+
+    pub fn mode(&self) -> GenerationMode {
+        self.mode
+    }
+
+    /// `strength` is how much more likely each weak key or sequence makes a word in adaptive mode,
+    /// 1.0 doubles the chance.
+    pub fn set_mode(&mut self, mode: GenerationMode, strength: f64) {
+        self.mode = mode;
+        self.strength = strength;
+    }
+
+    /// Trains the model for `level` if there is none for it yet.
+    fn train_markov(&mut self, level: MarkovLevel) {
+        if self.markov.as_ref().is_none_or(|m| m.level() != level) {
+            self.markov = Some(match level {
+                MarkovLevel::Characters => MarkovGenerator::characters(self.generator.words()),
+                MarkovLevel::Words => MarkovGenerator::load_words().unwrap_or_else(|e| {
+                    self.warning = Some(format!("failed to load the markov corpus: {}", e));
+                    MarkovGenerator::words("")
+                }),
+            });
+        }
+    }
+}
+
+impl TextSource for WordSource {
+    fn next_text(&mut self, statistic: &TypingStatistic) -> String {
+        let letters = match &mut self.lesson {
+            Some(lesson) => {
+                if lesson.update(statistic).is_some() {
+                    if let Err(e) = lesson.save() {
+                        self.warning = Some(format!("failed to save lesson progress: {}", e));
+                    }
+                }
+                lesson.letters()
+            }
+            None => vec![],
+        };
+
+        let (count, strength) = (self.words, self.strength);
+        let words = match self.mode {
+            GenerationMode::Markov(level) => {
+                self.train_markov(level);
+                let markov = self.markov.as_ref().unwrap();
+                let rng = &mut self.rng;
+                markov.generate(&letters, count, rng)
+            }
+            _ => {
+                let weak = match self.mode {
+                    GenerationMode::Adaptive => weak_spots(statistic),
+                    _ => WeakSpots::default(),
+                };
+                let (generator, rng) = (&self.generator, &mut self.rng);
+                generator.generate_adaptive(&letters, count, &weak, strength, rng)
+            }
+        };
+        // Text with other letters than the lesson's would defeat it, the label tells what is wrong.
+        match words {
+            Ok(words) => {
+                self.error = None;
+                self.augmentation.apply(words, &mut self.rng).join(" ")
+            }
+            Err(e) => {
+                self.error = Some(e);
+                String::new()
+            }
+        }
+    }
+
+    fn reseed(&mut self, seed: u64) {
+        self.rng = ChaCha8Rng::seed_from_u64(seed);
+    }
+
+    fn set_word_count(&mut self, words: usize) {
+        self.words = words;
+    }
+
+    fn label(&self) -> String {
+        let mut label = self.lesson.as_ref().map(|l| l.label()).unwrap_or_default();
+        if self.mode != GenerationMode::Uniform {
+            if !label.is_empty() {
+                label.push(' ');
+            }
+            label.push_str(&format!("({})", self.mode.description()));
+        }
+        let problems = self.error.iter().map(|e| e.to_string()).chain(self.warning.clone());
+        for problem in problems {
+            if !label.is_empty() {
+                label.push_str(", ");
+            }
+            label.push_str(&problem);
+        }
+        label
+    }
+}
+
+/// Slowest and most error-prone keys and bigrams and trigrams within words.
+fn weak_spots(statistic: &TypingStatistic) -> WeakSpots {
+    let mut keys: Vec<String> = statistic.slowest_keys(WEAK_SPOTS).into_iter()
+        .map(|(key, _)| key)
+        .chain(statistic.error_prone_keys(WEAK_SPOTS).into_iter().map(|(key, _)| key))
+        .filter(|key| key != " ")
+        .collect();
+    keys.sort();
+    keys.dedup();
+
+    let mut sequences: Vec<String> = [2, 3].iter()
+        .flat_map(|length| statistic.slowest_sequences(*length, WEAK_SPOTS)
+            .into_iter()
+            .chain(statistic.error_prone_sequences(*length, WEAK_SPOTS)))
+        .map(|s| s.sequence)
+        .filter(|s| !s.contains(' '))
+        .collect();
+    sequences.sort();
+    sequences.dedup();
+
+    WeakSpots { keys, sequences }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WORDS: &str = "alpha\nbravo\ncharlie\ndelta\necho\nfoxtrot\ngolf\nhotel\nindia\njuliett\nkilo\nlima";
+
+    #[test]
+    fn a_seed_gives_the_same_words() {
+        let generator = TextGenerator::new(WORDS);
+        let generate = |seed| generator.generate(&[], 20, &mut ChaCha8Rng::seed_from_u64(seed)).unwrap();
+        assert_eq!(generate(7), generate(7));
+        // Seeds are shared between builds, so the words may not change with the platform.
+        assert_eq!(generate(7)[..4].join(" "), "echo india india golf");
+
+        let statistic = TypingStatistic::new();
+        let texts = |seed| {
+            let mut source = WordSource::new(TextGenerator::new(WORDS), None, 20);
+            source.reseed(seed);
+            vec![source.next_text(&statistic), source.next_text(&statistic)]
+        };
+        assert_eq!(texts(7), texts(7));
+        assert_ne!(texts(7), texts(8));
+    }
+
+    #[test]
+    fn too_few_lesson_words_are_reported() {
+        let mut source = WordSource::new(TextGenerator::new(WORDS), Some(Lesson::default()), 20);
+        assert_eq!(source.next_text(&TypingStatistic::new()), "");
+        assert!(source.label().contains("only 0 words"), "{}", source.label());
+
+        source.set_lesson(None);
+        assert_ne!(source.next_text(&TypingStatistic::new()), "");
+        assert!(!source.label().contains("only"));
+    }
+}